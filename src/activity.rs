@@ -0,0 +1,122 @@
+use crate::{
+    bitboard::*,
+    board::Color::{self, *},
+    eval::Score,
+    params::EvalParams,
+    pawns::{pawn_attacks_all, relative_rank},
+    piece::{Piece, PieceType::*},
+    Chess,
+};
+
+// The number of safe squares of a piece with an average mobility, which scores zero.
+const MOBILITY_BASE: [i32; 6] = [0, 13, 7, 6, 4, 0];
+
+// Squares from White's point of view: a bishop on a7 or h7 gets shut in by a pawn on
+// b6 or g6.
+const TRAPPED_BISHOP_SQUARES: [(usize, usize); 2] = [(8, 17), (15, 22)];
+
+// Mobility and the rest of the piece terms, returned separately for the trace.
+pub fn evaluate(chess: &Chess, params: &EvalParams, color: Color) -> (Score, Score) {
+    let occupied = chess.occupied();
+    let pawns = chess.pieces_color(Pawn, color);
+    let enemy_pawns = chess.pieces_color(Pawn, !color);
+    let enemy_pawn_attacks = pawn_attacks_all(!color, enemy_pawns);
+    let mobility_area = !chess.colors[color as usize] & !enemy_pawn_attacks;
+
+    let mut mobility_score = Score::default();
+    let mut score = Score::default();
+
+    for &piece_type in &[Queen, Rook, Bishop, Knight] {
+        for sq in squares(chess.pieces_color(piece_type, color)) {
+            let attacks = Piece::new(piece_type, sq.into(), color).attacks(occupied);
+            let mobility = (attacks & mobility_area).count_ones() as i32;
+            mobility_score += params.mobility[piece_type as usize]
+                * (mobility - MOBILITY_BASE[piece_type as usize]);
+
+            match piece_type {
+                Rook => score += rook_activity(chess, params, color, sq, mobility),
+                Knight if is_outpost(color, sq, pawns, enemy_pawns) => {
+                    score += params.knight_outpost
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let bishops = chess.pieces_color(Bishop, color);
+    if bishops & LIGHT_SQUARES != EMPTY && bishops & !LIGHT_SQUARES != EMPTY {
+        score += params.bishop_pair
+    }
+
+    for &(bishop, pawn) in &TRAPPED_BISHOP_SQUARES {
+        let (bishop, pawn) = match color {
+            White => (bishop, pawn),
+            Black => (bishop ^ 56, pawn ^ 56),
+        };
+        if bishops & square_bb(bishop) != EMPTY && enemy_pawns & square_bb(pawn) != EMPTY {
+            score += params.trapped_bishop
+        }
+    }
+
+    (mobility_score, score)
+}
+
+fn rook_activity(
+    chess: &Chess,
+    params: &EvalParams,
+    color: Color,
+    sq: usize,
+    mobility: i32,
+) -> Score {
+    let mut score = Score::default();
+    let file = FILES[sq % 8];
+
+    if chess.pieces[Pawn as usize] & file == EMPTY {
+        score += params.rook_open_file
+    } else if chess.pieces_color(Pawn, color) & file == EMPTY {
+        score += params.rook_semi_open_file
+    }
+
+    // useful on the seventh when it eats pawns there or cuts off the king
+    if relative_rank(color, sq) == 6 {
+        let rank = 0xFF << (sq / 8 * 8);
+        let back_rank = match color {
+            White => 0xFF,
+            Black => 0xFF << 56,
+        };
+        if chess.pieces_color(Pawn, !color) & rank != EMPTY
+            || chess.pieces_color(King, !color) & back_rank != EMPTY
+        {
+            score += params.rook_seventh_rank
+        }
+    }
+
+    // boxed in the corner by a king that walked there instead of castling
+    if mobility <= 3 {
+        let castle_right = match color {
+            White => chess.white_castle,
+            Black => chess.black_castle,
+        };
+        if let Some(king) = squares(chess.pieces_color(King, color)).next() {
+            let (king_col, col) = (king % 8, sq % 8);
+            let trapped = if king_col < 4 {
+                col < king_col && !castle_right.can_queenside_castle()
+            } else {
+                col > king_col && !castle_right.can_kingside_castle()
+            };
+            if trapped && relative_rank(color, king) == 0 && king / 8 == sq / 8 {
+                score += params.trapped_rook
+            }
+        }
+    }
+
+    score
+}
+
+// A square in the enemy half, defended by a pawn, that no enemy pawn can ever attack.
+fn is_outpost(color: Color, sq: usize, pawns: Bitboard, enemy_pawns: Bitboard) -> bool {
+    let rank = relative_rank(color, sq);
+    (3..=5).contains(&rank)
+        && pawns & pawn_attacks(!color, sq) != EMPTY
+        && enemy_pawns & adjacent_files(sq % 8) & forward_ranks(color, sq / 8) == EMPTY
+}
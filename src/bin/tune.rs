@@ -0,0 +1,108 @@
+use std::{
+    env,
+    fs::File,
+    io::{BufRead, BufReader},
+    process,
+};
+
+use anyhow::{anyhow, bail, Result};
+use chess_engine2::{find_k, tunable_values, EvalParams, TuningEntry, TuningSet};
+
+const USAGE: &str = "usage: tune <positions> <output> [--params <file>] [--iterations <n>]
+
+Each line of <positions> holds a quiet position and the result of the game it comes
+from, either after the FEN as 1-0, 0-1, 1/2-1/2 or in brackets as [1.0], [0.5], [0.0].
+The tuned parameters are written to <output> after every iteration.";
+
+const DEFAULT_ITERATIONS: usize = 100;
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1)
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let mut paths = Vec::new();
+    let mut params = EvalParams::default();
+    let mut iterations = DEFAULT_ITERATIONS;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--params" => {
+                let path = args.next().ok_or_else(|| anyhow!(USAGE))?;
+                params = EvalParams::load(path)?
+            }
+            "--iterations" => iterations = args.next().ok_or_else(|| anyhow!(USAGE))?.parse()?,
+            "-h" | "--help" => bail!(USAGE),
+            _ => paths.push(arg),
+        }
+    }
+    let (input, output) = match paths.as_slice() {
+        [input, output] => (input, output),
+        _ => bail!(USAGE),
+    };
+
+    let entries = load_entries(input)?;
+    println!("{} positions", entries.len());
+    let set = TuningSet::new(entries);
+
+    let k = find_k(&set, &params);
+    let mut best = set.error(&params, k);
+    println!("K = {:.3}, error = {:.6}", k, best);
+
+    // Texel's local search: nudge every value by one in both directions and keep the
+    // changes that lower the error, until none does.
+    for iteration in 1..=iterations {
+        let mut improved = false;
+
+        for (field, index, eg) in tunable_values(&params) {
+            for &delta in &[1, -1] {
+                adjust(&mut params, field, index, eg, delta);
+                let error = set.error(&params, k);
+                if error < best {
+                    best = error;
+                    improved = true;
+                    break;
+                }
+                adjust(&mut params, field, index, eg, -delta);
+            }
+        }
+
+        params.save(output)?;
+        println!("iteration {}, error = {:.6}", iteration, best);
+
+        if !improved {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn load_entries(path: &str) -> Result<Vec<TuningEntry>> {
+    let mut entries = Vec::new();
+
+    for (number, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = TuningEntry::parse(&line)
+            .ok_or_else(|| anyhow!("error parsing line {}: {}", number + 1, line))?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn adjust(params: &mut EvalParams, field: usize, index: usize, eg: bool, delta: i32) {
+    let score = &mut params.field_mut(field)[index];
+    if eg {
+        score.eg += delta
+    } else {
+        score.mg += delta
+    }
+}
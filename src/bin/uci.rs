@@ -1,37 +1,87 @@
-use std::{convert::TryInto, io::stdin};
+use std::{
+    convert::TryFrom,
+    io::stdin,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
-use anyhow::Result;
+use anyhow::{Error, Result};
 use chess_engine2::{
     Chess,
     Color::{Black, White},
+    EvalParams, Move, SearchLimits, SearchResult, Searcher, Trace, DEFAULT_HASH_MB,
 };
 
-struct UCI {
-    chess: Chess,
+// Depth searched by a bare `go` without any limit.
+const DEFAULT_DEPTH: i32 = 5;
+
+// Check options turning the parts of the search on and off, for testing.
+const SEARCH_OPTIONS: [&str; 4] = ["NullMove", "LateMoveReductions", "PVS", "CheckExtensions"];
+
+struct Uci {
+    // none after an invalid fen or move, so that nothing is played from a position the
+    // GUI does not know about
+    chess: Option<Chess>,
+    searcher: Arc<Mutex<Searcher>>,
+    // a copy of the searcher's parameters, so that `eval` works during a search
+    params: EvalParams,
+    stop_flag: Arc<AtomicBool>,
+    ponder_flag: Arc<AtomicBool>,
+    search_thread: Option<JoinHandle<()>>,
 }
 
-impl UCI {
+impl Uci {
     fn new() -> Self {
-        UCI {
-            chess: Chess::new(),
+        let searcher = Searcher::new(DEFAULT_HASH_MB);
+        Uci {
+            chess: Some(Chess::new()),
+            stop_flag: searcher.stop_flag(),
+            ponder_flag: searcher.ponder_flag(),
+            params: searcher.params().clone(),
+            searcher: Arc::new(Mutex::new(searcher)),
+            search_thread: None,
         }
     }
 
     fn run(&mut self) -> Result<()> {
         println!("id name basic chess engine");
         println!("id author Federico Gaggero");
+        println!(
+            "option name Hash type spin default {} min 1 max 4096",
+            DEFAULT_HASH_MB
+        );
+        println!("option name Ponder type check default false");
+        println!("option name EvalFile type string default <empty>");
+        for name in &SEARCH_OPTIONS {
+            println!("option name {} type check default true", name);
+        }
         println!("uciok");
         loop {
             let mut input = String::new();
-            stdin().read_line(&mut input)?;
+            if stdin().read_line(&mut input)? == 0 {
+                self.stop();
+                break;
+            }
             let tokens: Vec<&str> = input.split_ascii_whitespace().collect();
-            match tokens[0] {
-                "quit" => break,
-                "stop" => (),
+            match tokens.first().copied().unwrap_or_default() {
+                "quit" => {
+                    self.stop();
+                    break;
+                }
+                "stop" => self.stop(),
+                "ponderhit" => self.ponder_flag.store(false, Ordering::SeqCst),
                 "isready" => println!("readyok"),
                 "ucinewgame" => self.ucinewgame(),
-                "position" => self.position(&tokens)?,
-                "go" => self.go(&tokens)?,
+                "setoption" => self.setoption(&tokens),
+                "position" => self.position(&tokens),
+                "go" => self.go(&tokens),
+                "perft" => self.perft(&tokens),
+                "eval" => self.eval(),
                 _ => continue,
             }
         }
@@ -39,10 +89,71 @@ impl UCI {
     }
 
     fn ucinewgame(&mut self) {
-        self.chess = Chess::new();
+        self.stop();
+        self.chess = Some(Chess::new());
+        self.searcher.lock().unwrap().tt.clear();
     }
 
-    fn position(&mut self, tokens: &[&str]) -> Result<()> {
+    fn setoption(&mut self, tokens: &[&str]) {
+        let name_index = tokens.iter().position(|&token| token == "name");
+        let value_index = tokens.iter().position(|&token| token == "value");
+
+        let (name, value) = match (name_index, value_index) {
+            (Some(name_index), Some(value_index)) if name_index < value_index => (
+                tokens[name_index + 1..value_index].join(" "),
+                tokens[value_index + 1..].join(" "),
+            ),
+            _ => return,
+        };
+
+        if name.eq_ignore_ascii_case("Hash") {
+            let mb = match value.parse::<usize>() {
+                Ok(mb) => mb.clamp(1, 4096),
+                Err(_) => {
+                    println!("info string invalid hash size {}", value);
+                    return;
+                }
+            };
+            self.stop();
+            self.searcher.lock().unwrap().tt.resize(mb)
+        } else if name.eq_ignore_ascii_case("EvalFile") {
+            let params = match value.as_str() {
+                "" | "<empty>" => EvalParams::default(),
+                path => match EvalParams::load(path) {
+                    Ok(params) => params,
+                    Err(err) => {
+                        println!("info string error loading {}: {}", path, err);
+                        return;
+                    }
+                },
+            };
+            self.stop();
+            self.searcher.lock().unwrap().set_params(params.clone());
+            self.params = params
+        } else if let Some(name) = SEARCH_OPTIONS
+            .iter()
+            .find(|option| name.eq_ignore_ascii_case(option))
+        {
+            let enabled = match value.to_ascii_lowercase().as_str() {
+                "true" | "on" | "1" => true,
+                "false" | "off" | "0" => false,
+                _ => {
+                    println!("info string invalid value {} for {}", value, name);
+                    return;
+                }
+            };
+            self.stop();
+            let options = &mut self.searcher.lock().unwrap().options;
+            match *name {
+                "NullMove" => options.null_move = enabled,
+                "LateMoveReductions" => options.late_move_reductions = enabled,
+                "PVS" => options.pvs = enabled,
+                _ => options.check_extensions = enabled,
+            }
+        }
+    }
+
+    fn position(&mut self, tokens: &[&str]) {
         let mut is_fen = false;
         let mut fen_tokens = Vec::new();
         let mut moves = Vec::new();
@@ -64,50 +175,190 @@ impl UCI {
         }
 
         if !fen_tokens.is_empty() {
-            self.chess = Chess::from_fen(&fen_tokens.join(" "))?;
+            match Chess::from_fen(&fen_tokens.join(" ")) {
+                Ok(chess) => self.chess = Some(chess),
+                Err(err) => {
+                    println!("info string invalid fen: {}", err);
+                    self.chess = None;
+                    return;
+                }
+            }
         }
 
+        let chess = match &mut self.chess {
+            Some(chess) => chess,
+            None => return,
+        };
+
         for r#move in moves {
-            self.chess.set(r#move.try_into()?)
+            let played = Move::try_from(r#move)
+                .and_then(|r#move| chess.try_play(r#move).map_err(Error::from));
+            if let Err(err) = played {
+                println!("info string {}", err);
+                self.chess = None;
+                return;
+            }
         }
+    }
 
-        Ok(())
+    fn eval(&self) {
+        match &self.chess {
+            Some(chess) => println!("{}", Trace::new(chess, &self.params)),
+            None => println!("info string no valid position"),
+        }
     }
 
-    fn go(&mut self, tokens: &[&str]) -> Result<()> {
-        let mut time = u64::MAX;
-        let mut is_movetime = false;
-        let side = self.chess.turn;
+    fn perft(&self, tokens: &[&str]) {
+        let chess = match &self.chess {
+            Some(chess) => chess,
+            None => {
+                println!("info string no valid position");
+                return;
+            }
+        };
+        let depth = match tokens.get(1) {
+            Some(depth) => match depth.parse() {
+                Ok(depth) => depth,
+                Err(_) => {
+                    println!("info string invalid depth {}", depth);
+                    return;
+                }
+            },
+            None => 1,
+        };
 
-        for &token in &tokens[1..] {
+        let mut nodes = 0;
+        for (r#move, count) in chess.divide(depth) {
+            println!("{}: {}", String::from(r#move), count);
+            nodes += count;
+        }
+        println!();
+        println!("Nodes searched: {}", nodes);
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        let mut limits = SearchLimits::default();
+        let mut ponder = false;
+
+        let mut tokens = tokens[1..].iter();
+        while let Some(&token) = tokens.next() {
             match token {
-                "wtime" if side == White => is_movetime = true,
-                "btime" if side == Black => is_movetime = true,
-                "movetime" => is_movetime = true,
-                _ => {
-                    if is_movetime {
-                        time = token.parse()?;
-                        is_movetime = false
-                    }
-                }
+                "wtime" => limits.wtime = parse_next(token, &mut tokens),
+                "btime" => limits.btime = parse_next(token, &mut tokens),
+                "winc" => limits.winc = parse_next(token, &mut tokens),
+                "binc" => limits.binc = parse_next(token, &mut tokens),
+                "movestogo" => limits.movestogo = parse_next(token, &mut tokens),
+                "movetime" => limits.movetime = parse_next(token, &mut tokens),
+                "depth" => limits.depth = parse_next(token, &mut tokens),
+                "nodes" => limits.nodes = parse_next(token, &mut tokens),
+                "infinite" => limits.infinite = true,
+                "ponder" => ponder = true,
+                _ => (),
             }
         }
 
-        eprint!("time: {}ms", time);
+        self.stop();
+        let chess = match &self.chess {
+            Some(chess) => chess.clone(),
+            None => {
+                println!("bestmove 0000");
+                return;
+            }
+        };
 
-        if let Some(r#move) = self.chess.get_best_move_uci(4) {
-            println!("bestmove {}", r#move)
-        } else {
-            println!("bestmove 0000")
+        let side_time = match chess.turn {
+            White => limits.wtime,
+            Black => limits.btime,
+        };
+        if !limits.infinite
+            && side_time.is_none()
+            && limits.movetime.is_none()
+            && limits.depth.is_none()
+            && limits.nodes.is_none()
+        {
+            limits.depth = Some(DEFAULT_DEPTH)
         }
 
-        Ok(())
+        self.stop_flag.store(false, Ordering::SeqCst);
+        self.ponder_flag.store(ponder, Ordering::SeqCst);
+
+        let searcher = Arc::clone(&self.searcher);
+        let stop = Arc::clone(&self.stop_flag);
+        let pondering = Arc::clone(&self.ponder_flag);
+
+        self.search_thread = Some(thread::spawn(move || {
+            let result = searcher
+                .lock()
+                .unwrap()
+                .search_with_info(&chess, &limits, print_info);
+
+            // the protocol forbids sending the move before the GUI asks for it
+            while (limits.infinite || pondering.load(Ordering::SeqCst))
+                && !stop.load(Ordering::SeqCst)
+            {
+                thread::sleep(Duration::from_millis(1))
+            }
+
+            if let Some(r#move) = result.best_move {
+                match result.pv.get(1) {
+                    Some(&ponder) if result.pv[0] == r#move => println!(
+                        "bestmove {} ponder {}",
+                        String::from(r#move),
+                        String::from(ponder)
+                    ),
+                    _ => println!("bestmove {}", String::from(r#move)),
+                }
+            } else {
+                println!("bestmove 0000")
+            }
+        }));
+    }
+
+    // Everything that needs the searcher stops the search first: only this thread reads
+    // the input, so an infinite or ponder search that is just waited for never ends.
+    fn stop(&mut self) {
+        self.stop_flag.store(true, Ordering::SeqCst);
+        if let Some(search_thread) = self.search_thread.take() {
+            search_thread.join().unwrap()
+        }
     }
 }
 
-fn main() -> Result<()> {
-    let mut uci = UCI::new();
-    uci.run().unwrap();
+fn print_info(info: &SearchResult) {
+    let score = match info.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", info.score),
+    };
+    let pv: Vec<String> = info.pv.iter().map(|&r#move| r#move.into()).collect();
 
-    Ok(())
+    println!(
+        "info depth {} seldepth {} score {} nodes {} nps {} time {} pv {}",
+        info.depth,
+        info.seldepth,
+        score,
+        info.nodes,
+        info.nps(),
+        info.elapsed.as_millis(),
+        pv.join(" ")
+    );
+}
+
+// A value the GUI got wrong is reported and ignored, like a missing one.
+fn parse_next<'a, T: FromStr>(
+    name: &str,
+    tokens: &mut impl Iterator<Item = &'a &'a str>,
+) -> Option<T> {
+    let token = tokens.next()?;
+    match token.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            println!("info string invalid {} {}", name, token);
+            None
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let mut uci = Uci::new();
+    uci.run()
 }
@@ -0,0 +1,179 @@
+use crate::board::Color;
+
+// Squares are indexed like `usize::from(Position)`: bit 0 is a8, bit 63 is h1.
+pub type Bitboard = u64;
+
+pub const EMPTY: Bitboard = 0;
+pub const LIGHT_SQUARES: Bitboard = 0xAA55_AA55_AA55_AA55;
+
+const KNIGHT_DELTAS: [(i32, i32); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+    (-1, -2),
+    (1, -2),
+];
+
+const KING_DELTAS: [(i32, i32); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+const WHITE_PAWN_DELTAS: [(i32, i32); 2] = [(-1, -1), (-1, 1)];
+const BLACK_PAWN_DELTAS: [(i32, i32); 2] = [(1, -1), (1, 1)];
+
+// The first four directions are the rook ones, the last four the bishop ones.
+const DIRECTIONS: [(i32, i32); 8] = KING_DELTAS;
+
+const KNIGHT_ATTACKS: [Bitboard; 64] = gen_leaper_attacks(&KNIGHT_DELTAS);
+const KING_ATTACKS: [Bitboard; 64] = gen_leaper_attacks(&KING_DELTAS);
+const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    gen_leaper_attacks(&WHITE_PAWN_DELTAS),
+    gen_leaper_attacks(&BLACK_PAWN_DELTAS),
+];
+const RAYS: [[Bitboard; 64]; 8] = gen_rays();
+
+const fn offset(sq: usize, row: i32, col: i32) -> Option<usize> {
+    let row = (sq / 8) as i32 + row;
+    let col = (sq % 8) as i32 + col;
+    if row < 0 || row > 7 || col < 0 || col > 7 {
+        None
+    } else {
+        Some((row * 8 + col) as usize)
+    }
+}
+
+const fn gen_leaper_attacks(deltas: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut attacks = [EMPTY; 64];
+    let mut sq = 0;
+    while sq < 64 {
+        let mut i = 0;
+        while i < deltas.len() {
+            if let Some(target) = offset(sq, deltas[i].0, deltas[i].1) {
+                attacks[sq] |= 1 << target;
+            }
+            i += 1;
+        }
+        sq += 1;
+    }
+    attacks
+}
+
+const fn gen_rays() -> [[Bitboard; 64]; 8] {
+    let mut rays = [[EMPTY; 64]; 8];
+    let mut dir = 0;
+    while dir < 8 {
+        let mut sq = 0;
+        while sq < 64 {
+            let mut current = sq;
+            while let Some(target) = offset(current, DIRECTIONS[dir].0, DIRECTIONS[dir].1) {
+                rays[dir][sq] |= 1 << target;
+                current = target;
+            }
+            sq += 1;
+        }
+        dir += 1;
+    }
+    rays
+}
+
+pub const fn square_bb(sq: usize) -> Bitboard {
+    1 << sq
+}
+
+pub fn knight_attacks(sq: usize) -> Bitboard {
+    KNIGHT_ATTACKS[sq]
+}
+
+pub fn king_attacks(sq: usize) -> Bitboard {
+    KING_ATTACKS[sq]
+}
+
+pub fn pawn_attacks(color: Color, sq: usize) -> Bitboard {
+    PAWN_ATTACKS[color as usize][sq]
+}
+
+fn ray_attacks(dir: usize, sq: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[dir][sq];
+    let blockers = ray & occupied;
+    if blockers == EMPTY {
+        return ray;
+    }
+
+    let (row, col) = DIRECTIONS[dir];
+    let blocker = if row > 0 || (row == 0 && col > 0) {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+
+    ray ^ RAYS[dir][blocker as usize]
+}
+
+pub fn rook_attacks(sq: usize, occupied: Bitboard) -> Bitboard {
+    (0..4).fold(EMPTY, |attacks, dir| attacks | ray_attacks(dir, sq, occupied))
+}
+
+pub fn bishop_attacks(sq: usize, occupied: Bitboard) -> Bitboard {
+    (4..8).fold(EMPTY, |attacks, dir| attacks | ray_attacks(dir, sq, occupied))
+}
+
+pub fn queen_attacks(sq: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(sq, occupied) | bishop_attacks(sq, occupied)
+}
+
+pub const FILES: [Bitboard; 8] = gen_files();
+
+const fn gen_files() -> [Bitboard; 8] {
+    let mut files = [EMPTY; 8];
+    let mut col = 0;
+    while col < 8 {
+        files[col] = 0x0101_0101_0101_0101 << col;
+        col += 1;
+    }
+    files
+}
+
+pub fn adjacent_files(col: usize) -> Bitboard {
+    let left = if col > 0 { FILES[col - 1] } else { EMPTY };
+    let right = if col < 7 { FILES[col + 1] } else { EMPTY };
+    left | right
+}
+
+// The ranks strictly in front of `row` from the point of view of `color`.
+pub fn forward_ranks(color: Color, row: usize) -> Bitboard {
+    match color {
+        Color::White => (1 << (row * 8)) - 1,
+        Color::Black if row == 7 => EMPTY,
+        Color::Black => !((1 << ((row + 1) * 8)) - 1),
+    }
+}
+
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
+
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == EMPTY {
+            None
+        } else {
+            let sq = self.0.trailing_zeros() as usize;
+            self.0 &= self.0 - 1;
+            Some(sq)
+        }
+    }
+}
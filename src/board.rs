@@ -1,9 +1,15 @@
-use core::panic;
-use std::{fmt::Display, ops::Not};
-
-use crate::{fen, negamax, piece::*, position::*};
-
-use anyhow::Result;
+use std::{error::Error, fmt::Display, ops::Not};
+
+use crate::{
+    bitboard::*,
+    eval::{self, Trace},
+    fen::{self, FenError},
+    params::{EvalParams, DEFAULT_PARAMS},
+    piece::*,
+    position::*,
+    search::{SearchLimits, SearchResult, Searcher},
+    zobrist,
+};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Color {
@@ -23,8 +29,6 @@ impl Not for Color {
     }
 }
 
-pub type Square = Option<Piece>;
-
 #[derive(Clone, Copy, Debug)]
 pub struct CastleRight {
     kingside: bool,
@@ -66,14 +70,56 @@ impl CastleRight {
 }
 
 #[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    captured: Option<Piece>,
+    white_castle: CastleRight,
+    black_castle: CastleRight,
+    en_passant: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    hash: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct IllegalMove(pub Move);
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "illegal move: {}", String::from(self.0))
+    }
+}
+
+impl Error for IllegalMove {}
+
+// Not `Copy` since it owns the history of the game, so copies are explicit clones.
+#[derive(Clone, Debug)]
 pub struct Chess {
-    pub board: [Square; 64],
+    pub pieces: [Bitboard; 6],
+    pub colors: [Bitboard; 2],
 
     pub white_castle: CastleRight,
     pub black_castle: CastleRight,
 
     pub en_passant: Option<Position>,
     pub turn: Color,
+
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+
+    // the game phase; the piece-square scores depend on the loaded parameters, so they
+    // are summed up by the evaluation instead
+    pub phase: i32,
+
+    pub hash: u64,
+    pub pawn_hash: u64,
+    // hashes of the positions the moves were played from, oldest first
+    pub history: Vec<u64>,
+}
+
+impl Default for Chess {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Chess {
@@ -81,213 +127,415 @@ impl Chess {
         fen::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap()
     }
 
-    pub fn from_fen(fen: &str) -> Result<Self> {
+    pub fn empty() -> Self {
+        Self {
+            pieces: [EMPTY; 6],
+            colors: [EMPTY; 2],
+            white_castle: CastleRight::new(),
+            black_castle: CastleRight::new(),
+            en_passant: None,
+            turn: White,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            phase: 0,
+            hash: 0,
+            pawn_hash: 0,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
         fen::parse(fen)
     }
 
-    pub fn set_at(&mut self, pos: Position, mut piece: Piece) {
-        piece.pos = pos;
-        self.board[usize::from(pos)] = Some(piece)
+    pub fn from_fen_lenient(fen: &str) -> Result<Self, FenError> {
+        fen::parse_lenient(fen)
     }
 
-    pub fn remove_at(&mut self, pos: Position) {
-        self.board[usize::from(pos)] = None
+    pub fn to_fen(&self) -> String {
+        fen::serialize(self)
     }
 
-    fn take_at(&mut self, pos: Position) -> Option<Piece> {
-        self.board[usize::from(pos)].take()
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[White as usize] | self.colors[Black as usize]
+    }
+
+    pub fn pieces_color(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[piece_type as usize] & self.colors[color as usize]
     }
 
-    fn get_all_pieces_turn(&self) -> Vec<Piece> {
-        self.board
+    pub fn piece_at(&self, pos: Position) -> Option<Piece> {
+        let bb = square_bb(usize::from(pos));
+
+        let color = if self.colors[White as usize] & bb != EMPTY {
+            White
+        } else if self.colors[Black as usize] & bb != EMPTY {
+            Black
+        } else {
+            return None;
+        };
+
+        PIECE_TYPES
             .iter()
-            .filter_map(|&square| square)
-            .filter(|piece| piece.color == self.turn)
-            .collect()
+            .find(|&&piece_type| self.pieces[piece_type as usize] & bb != EMPTY)
+            .map(|&piece_type| Piece::new(piece_type, pos, color))
     }
 
-    fn get_all_pieces(&self) -> Vec<Piece> {
-        self.board.iter().filter_map(|&square| square).collect()
+    // The piece on every square, a8 first, as the old `board` field held them.
+    pub fn board(&self) -> [Option<Piece>; 64] {
+        let mut board = [None; 64];
+        for (sq, square) in board.iter_mut().enumerate() {
+            *square = self.piece_at(sq.into())
+        }
+        board
+    }
+
+    pub fn set_at(&mut self, pos: Position, piece: Piece) {
+        self.remove_at(pos);
+
+        let bb = square_bb(usize::from(pos));
+        self.pieces[piece.r#type as usize] |= bb;
+        self.colors[piece.color as usize] |= bb;
+        self.phase += eval::phase_weight(piece.r#type);
+        self.hash ^= zobrist::piece_key(piece.r#type, piece.color, pos);
+        if piece.r#type == Pawn {
+            self.pawn_hash ^= zobrist::piece_key(Pawn, piece.color, pos)
+        }
+    }
+
+    pub fn remove_at(&mut self, pos: Position) {
+        self.take_at(pos);
+    }
+
+    fn take_at(&mut self, pos: Position) -> Option<Piece> {
+        let piece = self.piece_at(pos)?;
+
+        let bb = square_bb(usize::from(pos));
+        self.pieces[piece.r#type as usize] &= !bb;
+        self.colors[piece.color as usize] &= !bb;
+        self.phase -= eval::phase_weight(piece.r#type);
+        self.hash ^= zobrist::piece_key(piece.r#type, piece.color, pos);
+        if piece.r#type == Pawn {
+            self.pawn_hash ^= zobrist::piece_key(Pawn, piece.color, pos)
+        }
+
+        Some(piece)
+    }
+
+    fn get_pieces_color(&self, color: Color) -> impl Iterator<Item = Piece> + '_ {
+        PIECE_TYPES.iter().flat_map(move |&piece_type| {
+            squares(self.pieces_color(piece_type, color))
+                .map(move |sq| Piece::new(piece_type, sq.into(), color))
+        })
     }
 
     pub fn gen_moves(&self) -> Vec<Move> {
-        self.get_all_pieces_turn()
-            .iter()
-            .flat_map(|piece| piece.gen_moves(&self))
-            .collect()
+        let mut moves = Vec::with_capacity(64);
+        for piece in self.get_pieces_color(self.turn) {
+            piece.push_moves(self, &mut moves)
+        }
+        moves
     }
 
     pub fn gen_legal_moves(&self) -> Vec<Move> {
-        self.gen_moves()
-            .iter()
-            .filter(|&&r#move| !self.set_move(r#move).is_check())
-            .map(|&r#move| r#move)
+        self.filter_legal(self.gen_moves())
+    }
+
+    pub fn gen_legal_captures(&self) -> Vec<Move> {
+        let mut moves = self.gen_moves();
+        moves.retain(|&r#move| self.is_capture(r#move) || r#move.prom.is_some());
+        self.filter_legal(moves)
+    }
+
+    fn filter_legal(&self, moves: Vec<Move>) -> Vec<Move> {
+        let mut chess = self.without_history();
+        moves
+            .into_iter()
+            .filter(|&r#move| {
+                let undo = chess.make_move(r#move);
+                let is_legal = !chess.is_king_attacked(self.turn);
+                chess.unmake_move(r#move, undo);
+                is_legal
+            })
             .collect()
     }
 
+    pub fn is_capture(&self, r#move: Move) -> bool {
+        r#move.to.is_there_a_piece_color(self, !self.turn)
+            || (r#move.to.can_en_passant(self.en_passant)
+                && r#move
+                    .from
+                    .is_there_a_piece_type_color(self, Pawn, self.turn))
+    }
+
     pub fn get_king(&self) -> Option<Piece> {
-        self.get_all_pieces_turn()
-            .iter()
-            .find(|&&piece| piece.r#type == King && piece.color == self.turn)
-            .map(|&piece| piece)
+        squares(self.pieces_color(King, self.turn))
+            .next()
+            .map(|sq| Piece::new(King, sq.into(), self.turn))
     }
 
     pub fn is_check(&self) -> bool {
         if let Some(king) = self.get_king() {
-            king.am_i_being_attacked(&self.board)
+            king.am_i_being_attacked(self)
         } else {
             false
         }
     }
 
+    fn is_king_attacked(&self, color: Color) -> bool {
+        squares(self.pieces_color(King, color)).any(|sq| self.is_square_attacked(sq.into(), !color))
+    }
+
+    pub fn is_square_attacked(&self, pos: Position, by: Color) -> bool {
+        let sq = usize::from(pos);
+        let occupied = self.occupied();
+        let queens = self.pieces_color(Queen, by);
+
+        pawn_attacks(!by, sq) & self.pieces_color(Pawn, by) != EMPTY
+            || knight_attacks(sq) & self.pieces_color(Knight, by) != EMPTY
+            || king_attacks(sq) & self.pieces_color(King, by) != EMPTY
+            || bishop_attacks(sq, occupied) & (self.pieces_color(Bishop, by) | queens) != EMPTY
+            || rook_attacks(sq, occupied) & (self.pieces_color(Rook, by) | queens) != EMPTY
+    }
+
     pub fn set_move(mut self, r#move: Move) -> Self {
         self.apply_move(r#move);
         self
     }
 
     pub fn set(&mut self, r#move: Move) {
+        self.history.push(self.hash);
         self.apply_move(r#move);
         self.turn = !self.turn;
+        self.hash ^= zobrist::side_key();
+    }
+
+    pub fn try_play(&mut self, r#move: Move) -> Result<(), IllegalMove> {
+        if !self.gen_legal_moves().contains(&r#move) {
+            return Err(IllegalMove(r#move));
+        }
+
+        self.set(r#move);
+        Ok(())
+    }
+
+    // Unlike `try_play` this trusts the move, so it must come from the move generator:
+    // unmaking a move of an empty square would corrupt the board.
+    pub fn make_move(&mut self, r#move: Move) -> Undo {
+        debug_assert!(
+            self.piece_at(r#move.from).map(|piece| piece.color) == Some(self.turn),
+            "no piece of the side to move on {}",
+            String::from(r#move.from)
+        );
+
+        let captured = match self.piece_at(r#move.from) {
+            Some(piece) if piece.r#type == Pawn && r#move.to.can_en_passant(self.en_passant) => {
+                self.piece_at(r#move.to.down_color(piece.color, 1))
+            }
+            _ => self.piece_at(r#move.to),
+        };
+
+        let undo = Undo {
+            captured,
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+
+        self.set(r#move);
+        undo
+    }
+
+    pub fn unmake_move(&mut self, r#move: Move, undo: Undo) {
+        let Move { from, to, prom } = r#move;
+
+        self.turn = !self.turn;
+        self.history.pop();
+
+        if let Some(mut piece) = self.take_at(to) {
+            if prom.is_some() {
+                piece.r#type = Pawn
+            }
+
+            if piece.r#type == King && (to.col - from.col).abs() == 2 {
+                let (rook_from, rook_to) = if to.col > from.col {
+                    (from.right(3), from.right(1))
+                } else {
+                    (from.left(4), from.left(1))
+                };
+
+                if let Some(rook) = self.take_at(rook_to) {
+                    self.set_at(rook_from, rook)
+                }
+            }
+
+            self.set_at(from, piece);
+        }
+
+        if let Some(captured) = undo.captured {
+            self.set_at(captured.pos, captured)
+        }
+
+        self.white_castle = undo.white_castle;
+        self.black_castle = undo.black_castle;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.hash = undo.hash;
+    }
+
+    // Passes the turn, for the null move pruning. The clock is reset so that
+    // repetitions are not looked for across the null move.
+    pub fn make_null_move(&mut self) -> Undo {
+        let undo = Undo {
+            captured: None,
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            hash: self.hash,
+        };
+
+        self.history.push(self.hash);
+        self.hash ^= zobrist::en_passant_key(self) ^ zobrist::side_key();
+        self.en_passant = None;
+        self.halfmove_clock = 0;
+        self.turn = !self.turn;
+
+        debug_assert_eq!(self.hash, self.compute_hash());
+        undo
+    }
+
+    pub fn unmake_null_move(&mut self, undo: Undo) {
+        self.turn = !self.turn;
+        self.history.pop();
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.hash = undo.hash;
     }
 
-    //TODO: test if a move is legal
     pub fn apply_move(&mut self, r#move: Move) {
         let Move { from, to, prom } = r#move;
 
+        self.hash ^= self.castle_and_en_passant_key();
+
+        let is_capture = self.is_capture(r#move);
+
         if let Some(mut piece) = self.take_at(from) {
+            if piece.r#type == Pawn || is_capture {
+                self.halfmove_clock = 0
+            } else {
+                self.halfmove_clock += 1
+            }
+
+            if piece.color == Black {
+                self.fullmove_number += 1
+            }
+
             match piece.r#type {
-                King => {
-                    if self.turn == White && self.white_castle.can_castle() {
-                        if to == G1 {
-                            if let Some(rook) = self.take_at(piece.pos.right(3)) {
-                                self.set_at(piece.pos.right(2), piece);
-                                self.set_at(piece.pos.right(1), rook);
-
-                                self.white_castle.off()
-                            } else {
-                                panic!("must be a rook for castle or no kingside right")
-                            }
-                        } else if to == C1 {
-                            if let Some(rook) = self.take_at(piece.pos.left(4)) {
-                                self.set_at(piece.pos.left(2), piece);
-                                self.set_at(piece.pos.left(1), rook);
-
-                                self.white_castle.off()
-                            } else {
-                                panic!("must be a rook for castle or no queenside right")
-                            }
-                        } else {
-                            self.white_castle.off()
-                        }
-                    } else if self.turn == Black && self.black_castle.can_castle() {
-                        if to == G8 {
-                            if let Some(rook) = self.take_at(piece.pos.right(3)) {
-                                self.set_at(piece.pos.right(2), piece);
-                                self.set_at(piece.pos.right(1), rook);
-
-                                self.black_castle.off()
-                            } else {
-                                panic!("must be a rook for castle or no kingside right")
-                            }
-                        } else if to == C8 {
-                            if let Some(rook) = self.take_at(piece.pos.left(4)) {
-                                self.set_at(piece.pos.left(2), piece);
-                                self.set_at(piece.pos.left(1), rook);
-
-                                self.black_castle.off()
-                            } else {
-                                panic!("must be a rook for castle or no queenside right")
-                            }
-                        } else {
-                            self.black_castle.off()
-                        }
-                    }
-                }
-                Rook => {
-                    if self.turn == White {
-                        if self.white_castle.can_kingside_castle() && piece.pos.is_kingside_rook() {
-                            self.white_castle.kingside = false;
-                        } else if self.white_castle.can_queenside_castle()
-                            && piece.pos.is_queenside_rook()
-                        {
-                            self.white_castle.queenside = false;
-                        }
+                King if (to.col - from.col).abs() == 2 => {
+                    let (rook_from, rook_to) = if to.col > from.col {
+                        (from.right(3), from.right(1))
                     } else {
-                        if self.black_castle.can_kingside_castle() && piece.pos.is_kingside_rook() {
-                            self.black_castle.kingside = false;
-                        } else if self.black_castle.can_queenside_castle()
-                            && piece.pos.is_queenside_rook()
-                        {
-                            self.black_castle.queenside = false;
-                        }
+                        (from.left(4), from.left(1))
+                    };
+
+                    if let Some(rook) = self.take_at(rook_from) {
+                        self.set_at(rook_to, rook)
                     }
                 }
-                Pawn => {
-                    if let Some(en_passant) = self.en_passant {
-                        if to == en_passant {
-                            self.remove_at(en_passant.up_color(!self.turn, 1))
-                        }
-                    }
-                    if (from.row - to.row).abs() == 2 {
-                        self.en_passant = Some(to.down_color(self.turn, 1));
-                        self.set_at(to, piece);
-                        return;
-                    }
+                Pawn if to.can_en_passant(self.en_passant) => {
+                    self.remove_at(to.down_color(piece.color, 1))
                 }
                 _ => (),
             }
 
+            self.en_passant = if piece.r#type == Pawn && (from.row - to.row).abs() == 2 {
+                Some(from.up_color(piece.color, 1))
+            } else {
+                None
+            };
+
             if let Some(piece_type) = prom {
                 piece.r#type = piece_type
             }
 
-            if let Some(piece_captured) = self.take_at(to) {
-                if piece_captured.r#type == Rook {
-                    if self.turn == White {
-                        if self.black_castle.can_kingside_castle()
-                            && piece_captured.pos.is_kingside_rook()
-                        {
-                            self.black_castle.kingside = false;
-                        } else if self.black_castle.can_queenside_castle()
-                            && piece_captured.pos.is_queenside_rook()
-                        {
-                            self.black_castle.queenside = false;
-                        }
-                    } else {
-                        if self.white_castle.can_kingside_castle()
-                            && piece_captured.pos.is_kingside_rook()
-                        {
-                            self.white_castle.kingside = false;
-                        } else if self.white_castle.can_queenside_castle()
-                            && piece_captured.pos.is_queenside_rook()
-                        {
-                            self.white_castle.queenside = false;
-                        }
-                    }
-                }
-            }
-
             self.set_at(to, piece);
-            self.en_passant = None
+
+            self.update_castle_rights(from);
+            self.update_castle_rights(to);
+        }
+
+        self.hash ^= self.castle_and_en_passant_key();
+
+        debug_assert_eq!(self.hash, self.compute_hash());
+        debug_assert_eq!(self.pawn_hash, zobrist::compute_pawn_hash(self));
+        debug_assert_eq!(self.phase, eval::compute_phase(self));
+    }
+
+    fn castle_and_en_passant_key(&self) -> u64 {
+        zobrist::castle_key(self.white_castle, self.black_castle) ^ zobrist::en_passant_key(self)
+    }
+
+    pub fn compute_hash(&self) -> u64 {
+        zobrist::compute_hash(self)
+    }
+
+    fn update_castle_rights(&mut self, pos: Position) {
+        match pos {
+            E1 => self.white_castle.off(),
+            H1 => self.white_castle.kingside = false,
+            A1 => self.white_castle.queenside = false,
+            E8 => self.black_castle.off(),
+            H8 => self.black_castle.kingside = false,
+            A8 => self.black_castle.queenside = false,
+            _ => (),
+        }
+    }
+
+    // Copying the history is wasted work when moves are only tried out.
+    fn without_history(&self) -> Self {
+        Self {
+            pieces: self.pieces,
+            colors: self.colors,
+            white_castle: self.white_castle,
+            black_castle: self.black_castle,
+            en_passant: self.en_passant,
+            turn: self.turn,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            phase: self.phase,
+            hash: self.hash,
+            pawn_hash: self.pawn_hash,
+            history: Vec::new(),
         }
     }
 
     pub fn invert_turn(mut self) -> Self {
         self.turn = !self.turn;
+        self.hash ^= zobrist::side_key();
         self
     }
 
     pub fn evaluate(&self, color: Color) -> i32 {
-        self.get_all_pieces()
-            .iter()
-            .map(|piece| {
-                if piece.color == color {
-                    piece.evaluate()
-                } else {
-                    -piece.evaluate()
-                }
-            })
-            .sum()
+        self.evaluate_with(color, &DEFAULT_PARAMS)
+    }
+
+    pub fn evaluate_with(&self, color: Color, params: &EvalParams) -> i32 {
+        let score = eval::evaluate(self, params, None);
+        if color == White {
+            score
+        } else {
+            -score
+        }
+    }
+
+    pub fn evaluate_trace(&self) -> Trace {
+        Trace::new(self, &DEFAULT_PARAMS)
     }
 
     pub fn sort_moves(&self, legal_moves: Vec<Move>) -> Vec<(i32, Move)> {
@@ -298,9 +546,9 @@ impl Chess {
                     {
                         let Move { from, to, prom } = r#move;
                         let mut value = 0;
-                        let aggressor = self.board[usize::from(from)].unwrap();
+                        let aggressor = self.piece_at(from).unwrap();
 
-                        if let Some(victim) = self.board[usize::from(to)] {
+                        if let Some(victim) = self.piece_at(to) {
                             value += 10 * victim.r#type.evaluate_material()
                                 - aggressor.r#type.evaluate_material()
                         }
@@ -321,30 +569,18 @@ impl Chess {
         ordered_moves
     }
 
-    pub fn get_best_move(&self, depth: i32) -> Option<Move> {
-        let legal_moves = self.gen_legal_moves();
-        let mut r#move = None;
-        let mut alpha = i32::MIN;
-        let beta = i32::MAX;
-
-        let ordered_moves = self.sort_moves(legal_moves);
-
-        for (_, legal_move) in ordered_moves {
-            let eval = !negamax(
-                self.set_move(legal_move).invert_turn(),
-                !beta,
-                !alpha,
-                depth,
-                !self.turn,
-            );
-
-            if eval > alpha {
-                alpha = eval;
-                r#move = Some(legal_move);
-            }
-        }
+    // The tables are thrown away after the search, so they are kept small; callers
+    // searching many positions should keep a `Searcher` instead.
+    pub fn search(&self, limits: &SearchLimits) -> SearchResult {
+        Searcher::new(1).search(self, limits)
+    }
 
-        r#move
+    pub fn get_best_move(&self, depth: i32) -> Option<Move> {
+        let limits = SearchLimits {
+            depth: Some(depth + 1),
+            ..SearchLimits::default()
+        };
+        self.search(&limits).best_move
     }
 
     pub fn get_best_move_uci(&self, depth: i32) -> Option<String> {
@@ -354,20 +590,17 @@ impl Chess {
 
 impl Display for Chess {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        print!("8");
-        for (i, square) in self.board.iter().enumerate() {
-            if i != 0 && i % 8 == 0 {
-                print!("\n{}", 8 - i / 8)
-            };
-
-            if let Some(piece) = square {
-                write!(f, "{}", piece)?
-            } else {
-                print!(" ")
+        for row in 0..8 {
+            write!(f, "{}", 8 - row)?;
+            for col in 0..8 {
+                if let Some(piece) = self.piece_at(Position::from(row * 8 + col)) {
+                    write!(f, "{}", piece)?
+                } else {
+                    write!(f, " ")?
+                }
             }
+            writeln!(f)?;
         }
-        print!("\n ABCDEFGH");
-
-        Ok(())
+        write!(f, " ABCDEFGH")
     }
 }
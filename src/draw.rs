@@ -0,0 +1,50 @@
+use crate::{
+    bitboard::{EMPTY, LIGHT_SQUARES},
+    Chess,
+    PieceType::*,
+};
+
+impl Chess {
+    // The position already occurred with the same side to move.
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions() >= 1
+    }
+
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 2
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        // a mate delivered on the hundredth halfmove still counts
+        self.halfmove_clock >= 100 && !(self.is_check() && self.gen_legal_moves().is_empty())
+    }
+
+    // Neither side can mate, whatever the moves played.
+    pub fn is_insufficient_material(&self) -> bool {
+        if self.pieces[Pawn as usize] | self.pieces[Rook as usize] | self.pieces[Queen as usize]
+            != EMPTY
+        {
+            return false;
+        }
+
+        let bishops = self.pieces[Bishop as usize];
+        let minors = bishops | self.pieces[Knight as usize];
+
+        minors.count_ones() <= 1
+            || minors == bishops
+                && (bishops & LIGHT_SQUARES == EMPTY || bishops & !LIGHT_SQUARES == EMPTY)
+    }
+
+    pub(crate) fn repetitions(&self) -> usize {
+        // positions before the last capture or pawn move cannot come back, and only
+        // every other one has the same side to move
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .filter(|&&hash| hash == self.hash)
+            .count()
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::{
+    activity,
+    bitboard::*,
+    board::Color::{self, *},
+    king_safety,
+    params::EvalParams,
+    pawns::{self, pawn_attacks_all, PawnEntry, PawnTable},
+    piece::{PieceType, PieceType::*, PIECE_TYPES},
+    Chess,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    // Interpolates between the middlegame and the endgame value, `phase` going from
+    // MAX_PHASE with all the pieces on the board down to 0 with only kings and pawns.
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl Sub for Score {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        Self::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other
+    }
+}
+
+pub const MAX_PHASE: i32 = 24;
+
+const PHASE_WEIGHTS: [i32; 6] = [0, 4, 2, 1, 1, 0];
+
+pub fn phase_weight(piece_type: PieceType) -> i32 {
+    PHASE_WEIGHTS[piece_type as usize]
+}
+
+// The incrementally updated value, computed from scratch.
+pub fn compute_phase(chess: &Chess) -> i32 {
+    PIECE_TYPES
+        .iter()
+        .map(|&piece_type| {
+            chess.pieces[piece_type as usize].count_ones() as i32 * phase_weight(piece_type)
+        })
+        .sum()
+}
+
+// From White's point of view. The pawn structure is looked up in `pawn_table` when
+// there is one and computed from scratch otherwise.
+pub fn evaluate(chess: &Chess, params: &EvalParams, pawn_table: Option<&mut PawnTable>) -> i32 {
+    let pawns = match pawn_table {
+        Some(pawn_table) => pawn_table.probe(chess, params),
+        None => PawnEntry::new(chess, params),
+    };
+
+    (evaluate_color(chess, params, White, &pawns) - evaluate_color(chess, params, Black, &pawns))
+        .taper(chess.phase)
+}
+
+fn evaluate_color(chess: &Chess, params: &EvalParams, color: Color, pawns: &PawnEntry) -> Score {
+    evaluate_terms(chess, params, color, pawns)
+        .iter()
+        .fold(Score::default(), |score, &term| score + term)
+}
+
+// Indexed by `Term`.
+fn evaluate_terms(
+    chess: &Chess,
+    params: &EvalParams,
+    color: Color,
+    pawns: &PawnEntry,
+) -> [Score; TERMS.len()] {
+    let mut material = Score::default();
+    let mut psqt = Score::default();
+    for &piece_type in &PIECE_TYPES {
+        let pieces = chess.pieces_color(piece_type, color);
+        material += params.material[piece_type as usize] * pieces.count_ones() as i32;
+        for sq in squares(pieces) {
+            psqt += params.psqt(piece_type, color, sq)
+        }
+    }
+
+    let (mobility, pieces) = activity::evaluate(chess, params, color);
+
+    let mut terms = [Score::default(); TERMS.len()];
+    terms[Term::Material as usize] = material;
+    terms[Term::Psqt as usize] = psqt;
+    terms[Term::Pawns as usize] = pawns.score[color as usize];
+    terms[Term::PassedPawns as usize] =
+        pawns::evaluate_passed(chess, params, color, pawns.passed[color as usize]);
+    terms[Term::KingSafety as usize] = king_safety::evaluate(chess, params, color);
+    terms[Term::Mobility as usize] = mobility;
+    terms[Term::Pieces as usize] = pieces;
+    terms
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Term {
+    Material,
+    Psqt,
+    Pawns,
+    PassedPawns,
+    KingSafety,
+    Mobility,
+    Pieces,
+}
+
+pub const TERMS: [Term; 7] = [
+    Term::Material,
+    Term::Psqt,
+    Term::Pawns,
+    Term::PassedPawns,
+    Term::KingSafety,
+    Term::Mobility,
+    Term::Pieces,
+];
+
+impl Display for Term {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            Term::Material => "Material",
+            Term::Psqt => "Piece-square",
+            Term::Pawns => "Pawns",
+            Term::PassedPawns => "Passed pawns",
+            Term::KingSafety => "King safety",
+            Term::Mobility => "Mobility",
+            Term::Pieces => "Pieces",
+        };
+        f.pad(name)
+    }
+}
+
+// The evaluation split into its terms, both colors from their own point of view.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Trace {
+    pub terms: [[Score; 2]; TERMS.len()],
+    pub phase: i32,
+}
+
+impl Trace {
+    pub fn new(chess: &Chess, params: &EvalParams) -> Self {
+        let pawns = PawnEntry::new(chess, params);
+        let mut terms = [[Score::default(); 2]; TERMS.len()];
+
+        for &color in &[White, Black] {
+            let color_terms = evaluate_terms(chess, params, color, &pawns);
+            for &term in &TERMS {
+                terms[term as usize][color as usize] = color_terms[term as usize]
+            }
+        }
+
+        Self {
+            terms,
+            phase: chess.phase,
+        }
+    }
+
+    // White minus Black, not tapered yet.
+    pub fn term(&self, term: Term) -> Score {
+        let [white, black] = self.terms[term as usize];
+        white - black
+    }
+
+    // From White's point of view, the same as `eval::evaluate`.
+    pub fn total(&self) -> i32 {
+        TERMS
+            .iter()
+            .fold(Score::default(), |total, &term| total + self.term(term))
+            .taper(self.phase)
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let line = "-------------+-------------+-------------+------------";
+        writeln!(f, "        Term |    White    |    Black    |    Total")?;
+        writeln!(f, "             |   MG    EG  |   MG    EG  |   MG    EG")?;
+        writeln!(f, "{}", line)?;
+
+        let mut totals = [Score::default(); 2];
+        let row = |f: &mut Formatter, name: &dyn Display, white: Score, black: Score| {
+            let total = white - black;
+            writeln!(
+                f,
+                "{:>12} | {:>5} {:>5} | {:>5} {:>5} | {:>5} {:>5}",
+                name, white.mg, white.eg, black.mg, black.eg, total.mg, total.eg
+            )
+        };
+
+        for &term in &TERMS {
+            let [white, black] = self.terms[term as usize];
+            totals[0] += white;
+            totals[1] += black;
+            row(f, &term, white, black)?;
+        }
+
+        writeln!(f, "{}", line)?;
+        row(f, &"Total", totals[0], totals[1])?;
+        writeln!(f)?;
+        writeln!(f, "Phase: {}/{}", self.phase.min(MAX_PHASE), MAX_PHASE)?;
+        write!(f, "Evaluation: {} (White's point of view)", self.total())
+    }
+}
+
+pub fn attacks_by(chess: &Chess, color: Color) -> Bitboard {
+    let occupied = chess.occupied();
+    let mut attacks = pawn_attacks_all(color, chess.pieces_color(Pawn, color));
+
+    for sq in squares(chess.pieces_color(Knight, color)) {
+        attacks |= knight_attacks(sq)
+    }
+    for sq in squares(chess.pieces_color(Bishop, color) | chess.pieces_color(Queen, color)) {
+        attacks |= bishop_attacks(sq, occupied)
+    }
+    for sq in squares(chess.pieces_color(Rook, color) | chess.pieces_color(Queen, color)) {
+        attacks |= rook_attacks(sq, occupied)
+    }
+    for sq in squares(chess.pieces_color(King, color)) {
+        attacks |= king_attacks(sq)
+    }
+
+    attacks
+}
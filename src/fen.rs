@@ -1,76 +1,324 @@
-use std::convert::TryInto;
+use std::{
+    convert::TryInto,
+    error::Error,
+    fmt::{self, Display, Write},
+};
 
 use crate::{
-    board::{CastleRight, Chess},
-    Color::*,
+    board::Chess,
+    position::*,
+    Color::{self, *},
     {Piece, PieceType::*}
 };
-use anyhow::{bail, Result};
-
-pub fn parse(fen: &str) -> Result<Chess> {
-    let mut chess = Chess {
-        board: [None; 64],
-        white_castle: CastleRight::new(),
-        black_castle: CastleRight::new(),
-        en_passant: None,
-        turn: White,
-    };
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum FenError {
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    BadRankLength(usize),
+    InvalidPiece(char),
+    InvalidSide(String),
+    InvalidCastling(String),
+    InconsistentCastling(char),
+    InvalidEnPassant(String),
+    ImpossibleEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    MissingKing(Color),
+    TooManyKings(Color),
+    PawnOnBackRank(String),
+    SideNotToMoveInCheck,
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "expected 6 fields, found {}", count),
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::BadRankLength(rank) => write!(f, "rank {} is not 8 files long", rank),
+            FenError::InvalidPiece(chr) => write!(f, "found illegal piece char: {}", chr),
+            FenError::InvalidSide(side) => write!(f, "found illegal side to move: {}", side),
+            FenError::InvalidCastling(castling) => {
+                write!(f, "found illegal castling field: {}", castling)
+            }
+            FenError::InconsistentCastling(chr) => {
+                write!(f, "castling right {} without king and rook in place", chr)
+            }
+            FenError::InvalidEnPassant(en_passant) => {
+                write!(f, "found illegal en passant field: {}", en_passant)
+            }
+            FenError::ImpossibleEnPassant(en_passant) => {
+                write!(f, "en passant on {} is impossible", en_passant)
+            }
+            FenError::InvalidHalfmoveClock(clock) => {
+                write!(f, "found illegal halfmove clock: {}", clock)
+            }
+            FenError::InvalidFullmoveNumber(number) => {
+                write!(f, "found illegal fullmove number: {}", number)
+            }
+            FenError::MissingKing(color) => write!(f, "{:?} has no king", color),
+            FenError::TooManyKings(color) => write!(f, "{:?} has more than one king", color),
+            FenError::PawnOnBackRank(pos) => write!(f, "found a pawn on the back rank: {}", pos),
+            FenError::SideNotToMoveInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+pub fn parse(fen: &str) -> Result<Chess, FenError> {
+    parse_with(fen, true)
+}
+
+// Accepts FENs without the move counters, ignores castling rights and en passant
+// squares that cannot apply to the position, and skips the legality checks.
+pub fn parse_lenient(fen: &str) -> Result<Chess, FenError> {
+    parse_with(fen, false)
+}
+
+fn parse_with(fen: &str, strict: bool) -> Result<Chess, FenError> {
+    let mut chess = Chess::empty();
 
     let tokens: Vec<&str> = fen.split_ascii_whitespace().collect();
 
-    if tokens.len() != 6 {
-        bail!("Error parsing FEN: {}", fen)
+    if tokens.len() != 6 && (strict || tokens.len() < 4 || tokens.len() > 6) {
+        return Err(FenError::WrongFieldCount(tokens.len()));
     }
 
-    for (i, rank) in tokens[0].split("/").enumerate() {
+    let ranks: Vec<&str> = tokens[0].split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongRankCount(ranks.len()));
+    }
+
+    for (row, rank) in ranks.iter().enumerate() {
         let mut col = 0;
         for chr in rank.chars() {
-            if let Some(num) = chr.to_digit(10) {
-                col += num
+            if let Some(num @ 1..=8) = chr.to_digit(10) {
+                col += num as usize
             } else {
-                let index = i * 8 + col as usize;
-                chess.board[index] = Some(match chr {
-                    'P' => {Piece::new(Pawn, index.into(), White)}
-                    'N' => {Piece::new(Knight, index.into(), White)}
-                    'B' => {Piece::new(Bishop, index.into(), White)}
-                    'R' => {Piece::new(Rook, index.into(), White)}
-                    'Q' => {Piece::new(Queen, index.into(), White)}
-                    'K' => {Piece::new(King, index.into(), White)}
-                    'p' => {Piece::new(Pawn, index.into(), Black)}
-                    'n' => {Piece::new(Knight, index.into(), Black)}
-                    'b' => {Piece::new(Bishop, index.into(), Black)}
-                    'r' => {Piece::new(Rook, index.into(), Black)}
-                    'q' => {Piece::new(Queen, index.into(), Black)}
-                    'k' => {Piece::new(King, index.into(), Black)}
-                    _ => bail!("found illegal char: {}", chr),
-                });
+                let color = if chr.is_ascii_uppercase() {
+                    White
+                } else {
+                    Black
+                };
+                let piece_type = match chr.to_ascii_lowercase() {
+                    'p' => Pawn,
+                    'n' => Knight,
+                    'b' => Bishop,
+                    'r' => Rook,
+                    'q' => Queen,
+                    'k' => King,
+                    _ => return Err(FenError::InvalidPiece(chr)),
+                };
+
+                if col >= 8 {
+                    return Err(FenError::BadRankLength(8 - row));
+                }
+
+                let pos = Position::from(row * 8 + col);
+                chess.set_at(pos, Piece::new(piece_type, pos, color));
                 col += 1
             }
         }
+
+        if col != 8 {
+            return Err(FenError::BadRankLength(8 - row));
+        }
     }
 
-    chess.turn = match tokens[1].as_bytes()[0] as char {
-        'w' => White,
-        'b' => Black,
-        chr @ _ => bail!("found illegal char: {}", chr)
+    chess.turn = match tokens[1] {
+        "w" => White,
+        "b" => Black,
+        side => return Err(FenError::InvalidSide(side.to_string())),
     };
 
-    if tokens[2].contains('K') {
-        chess.white_castle.set_kingside_castle_on()
-    }
-    if tokens[2].contains('Q') {
-        chess.white_castle.set_queenside_castle_on()
+    parse_castling(&mut chess, tokens[2], strict)?;
+    parse_en_passant(&mut chess, tokens[3], strict)?;
+
+    if let Some(&clock) = tokens.get(4) {
+        chess.halfmove_clock = clock
+            .parse()
+            .map_err(|_| FenError::InvalidHalfmoveClock(clock.to_string()))?;
     }
-    if tokens[2].contains('k') {
-        chess.black_castle.set_kingside_castle_on()
+    if let Some(&number) = tokens.get(5) {
+        chess.fullmove_number = match number.parse() {
+            Ok(number) if number > 0 => number,
+            _ => return Err(FenError::InvalidFullmoveNumber(number.to_string())),
+        };
     }
-    if tokens[2].contains('q') {
-        chess.black_castle.set_queenside_castle_on()
+
+    if strict {
+        validate(&chess)?;
     }
 
-    if let Ok(en_passant) = tokens[3].try_into() {
-        chess.en_passant = Some(en_passant)
-    } 
+    chess.hash = chess.compute_hash();
 
     Ok(chess)
 }
+
+fn parse_castling(chess: &mut Chess, castling: &str, strict: bool) -> Result<(), FenError> {
+    if castling == "-" {
+        return Ok(());
+    }
+
+    let is_canonical = "KQkq"
+        .chars()
+        .filter(|&chr| castling.contains(chr))
+        .eq(castling.chars());
+    if castling.chars().any(|chr| !"KQkq".contains(chr)) || (strict && !is_canonical) {
+        return Err(FenError::InvalidCastling(castling.to_string()));
+    }
+
+    for chr in castling.chars() {
+        let (color, king, rook) = match chr {
+            'K' => (White, E1, H1),
+            'Q' => (White, E1, A1),
+            'k' => (Black, E8, H8),
+            _ => (Black, E8, A8),
+        };
+
+        if !king.is_there_a_piece_type_color(chess, King, color)
+            || !rook.is_there_a_piece_type_color(chess, Rook, color)
+        {
+            if strict {
+                return Err(FenError::InconsistentCastling(chr));
+            }
+            continue;
+        }
+
+        match chr {
+            'K' => chess.white_castle.set_kingside_castle_on(),
+            'Q' => chess.white_castle.set_queenside_castle_on(),
+            'k' => chess.black_castle.set_kingside_castle_on(),
+            _ => chess.black_castle.set_queenside_castle_on(),
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_en_passant(chess: &mut Chess, en_passant: &str, strict: bool) -> Result<(), FenError> {
+    if en_passant == "-" {
+        return Ok(());
+    }
+
+    let pos: Position = match en_passant.try_into() {
+        Ok(pos) if en_passant.len() == 2 => pos,
+        _ if strict => return Err(FenError::InvalidEnPassant(en_passant.to_string())),
+        _ => return Ok(()),
+    };
+
+    // the pawn that just moved two squares stands in front of the en passant square,
+    // and the square it came from must be empty
+    let pusher = !chess.turn;
+    let is_possible = pos.row == if pusher == White { 5 } else { 2 }
+        && !pos.is_there_a_piece(chess)
+        && !pos.down_color(pusher, 1).is_there_a_piece(chess)
+        && pos
+            .up_color(pusher, 1)
+            .is_there_a_piece_type_color(chess, Pawn, pusher);
+
+    if is_possible {
+        chess.en_passant = Some(pos)
+    } else if strict {
+        return Err(FenError::ImpossibleEnPassant(en_passant.to_string()));
+    }
+
+    Ok(())
+}
+
+fn validate(chess: &Chess) -> Result<(), FenError> {
+    for &color in &[White, Black] {
+        match chess.pieces_color(King, color).count_ones() {
+            0 => return Err(FenError::MissingKing(color)),
+            1 => (),
+            _ => return Err(FenError::TooManyKings(color)),
+        }
+    }
+
+    for col in 0..8 {
+        for &row in &[0, 7] {
+            let pos = Position::from(row * 8 + col);
+            if chess
+                .piece_at(pos)
+                .is_some_and(|piece| piece.r#type == Pawn)
+            {
+                return Err(FenError::PawnOnBackRank(pos.into()));
+            }
+        }
+    }
+
+    if chess.clone().invert_turn().is_check() {
+        return Err(FenError::SideNotToMoveInCheck);
+    }
+
+    Ok(())
+}
+
+pub fn serialize(chess: &Chess) -> String {
+    let mut fen = String::new();
+
+    for row in 0..8 {
+        if row != 0 {
+            fen.push('/')
+        }
+
+        let mut empty = 0;
+        for col in 0..8 {
+            match chess.piece_at(Position::from(row * 8 + col)) {
+                Some(piece) => {
+                    if empty != 0 {
+                        write!(fen, "{}", empty).unwrap();
+                        empty = 0
+                    }
+                    fen.push(piece_char(piece))
+                }
+                None => empty += 1,
+            }
+        }
+        if empty != 0 {
+            write!(fen, "{}", empty).unwrap()
+        }
+    }
+
+    fen.push_str(if chess.turn == White { " w " } else { " b " });
+
+    let castles = [
+        (chess.white_castle.can_kingside_castle(), 'K'),
+        (chess.white_castle.can_queenside_castle(), 'Q'),
+        (chess.black_castle.can_kingside_castle(), 'k'),
+        (chess.black_castle.can_queenside_castle(), 'q'),
+    ];
+    let castles: String = castles
+        .iter()
+        .filter(|(can_castle, _)| *can_castle)
+        .map(|&(_, chr)| chr)
+        .collect();
+    fen.push_str(if castles.is_empty() { "-" } else { &castles });
+
+    match chess.en_passant {
+        Some(en_passant) => write!(fen, " {}", String::from(en_passant)).unwrap(),
+        None => fen.push_str(" -"),
+    }
+
+    write!(fen, " {} {}", chess.halfmove_clock, chess.fullmove_number).unwrap();
+
+    fen
+}
+
+fn piece_char(piece: Piece) -> char {
+    let chr = match piece.r#type {
+        King => 'k',
+        Queen => 'q',
+        Rook => 'r',
+        Bishop => 'b',
+        Knight => 'n',
+        Pawn => 'p',
+    };
+
+    if piece.color == White {
+        chr.to_ascii_uppercase()
+    } else {
+        chr
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    bitboard::*,
+    board::Color::{self, *},
+    eval::{self, Score},
+    params::EvalParams,
+    piece::PieceType::*,
+    Chess, PIECE_TYPES,
+};
+
+const ATTACK_WEIGHTS: [i32; 6] = [0, 5, 3, 2, 2, 0];
+const MAX_ATTACK_UNITS: i32 = 60;
+
+// Non-pawn material of one side at the start of the game, in phase units.
+const FULL_MATERIAL: i32 = eval::MAX_PHASE / 2;
+
+// The king safety of `color`, a penalty growing with the attacks on the squares around
+// the king and the holes in front of it.
+pub fn evaluate(chess: &Chess, params: &EvalParams, color: Color) -> Score {
+    let king = match squares(chess.pieces_color(King, color)).next() {
+        Some(king) => king,
+        None => return Score::default(),
+    };
+
+    let enemy_material: i32 = PIECE_TYPES
+        .iter()
+        .map(|&piece_type| {
+            chess.pieces_color(piece_type, !color).count_ones() as i32
+                * eval::phase_weight(piece_type)
+        })
+        .sum();
+    if enemy_material == 0 {
+        return Score::default();
+    }
+
+    // `king_attack` is in hundredths per squared attack unit
+    let attack = params.king_attack * attack_units(chess, color, king).pow(2);
+    let penalty =
+        pawn_shelter(chess, params, color, king) - Score::new(attack.mg / 100, attack.eg / 100);
+    let scale = enemy_material.min(FULL_MATERIAL);
+
+    Score::new(
+        penalty.mg * scale / FULL_MATERIAL,
+        penalty.eg * scale / FULL_MATERIAL,
+    )
+}
+
+// A single attacker is rarely dangerous, so attacks only count from the second one on.
+fn attack_units(chess: &Chess, color: Color, king: usize) -> i32 {
+    let zone = king_attacks(king) | square_bb(king);
+    let occupied = chess.occupied();
+
+    let mut attackers = 0;
+    let mut units = 0;
+
+    for &piece_type in &[Queen, Rook, Bishop, Knight] {
+        for sq in squares(chess.pieces_color(piece_type, !color)) {
+            let attacks = match piece_type {
+                Queen => queen_attacks(sq, occupied),
+                Rook => rook_attacks(sq, occupied),
+                Bishop => bishop_attacks(sq, occupied),
+                _ => knight_attacks(sq),
+            } & zone;
+
+            if attacks != EMPTY {
+                attackers += 1;
+                units += ATTACK_WEIGHTS[piece_type as usize] * attacks.count_ones() as i32
+            }
+        }
+    }
+
+    if attackers < 2 {
+        0
+    } else {
+        units.min(MAX_ATTACK_UNITS)
+    }
+}
+
+// Penalties for the files around the king without a pawn right in front of it.
+fn pawn_shelter(chess: &Chess, params: &EvalParams, color: Color, king: usize) -> Score {
+    let (row, col) = (king / 8, king % 8);
+    let pawns = chess.pieces_color(Pawn, color);
+    let all_pawns = chess.pieces[Pawn as usize];
+    let ahead = forward_ranks(color, row);
+
+    let mut penalty = Score::default();
+
+    for &file in &FILES[col.saturating_sub(1)..=(col + 1).min(7)] {
+        let shield = pawns & file & ahead;
+
+        // the closest pawn in front of the king
+        let distance = match (color, shield) {
+            (_, EMPTY) => None,
+            (White, shield) => Some(row - (63 - shield.leading_zeros() as usize) / 8),
+            (Black, shield) => Some(shield.trailing_zeros() as usize / 8 - row),
+        };
+
+        penalty += match distance {
+            Some(1) => Score::default(),
+            Some(2) => params.shield_advanced,
+            _ => params.shield_missing,
+        };
+
+        if all_pawns & file == EMPTY {
+            penalty += params.open_file
+        }
+    }
+
+    penalty
+}
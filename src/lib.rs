@@ -1,47 +1,34 @@
+mod activity;
+mod bitboard;
 mod board;
+mod draw;
+mod eval;
 mod fen;
+mod king_safety;
+mod outcome;
+mod params;
+mod pawns;
+mod perft;
+mod pgn;
 mod piece;
 mod position;
+mod san;
+mod search;
+mod texel;
+mod tt;
+mod zobrist;
 
-use std::{
-    cmp::max,
-};
-
-pub use board::Chess;
+pub use bitboard::Bitboard;
 pub use board::Color;
-pub use piece::{Piece, PieceType};
+pub use board::{Chess, IllegalMove, Undo};
+pub use eval::{Score, Term, Trace, MAX_PHASE, TERMS};
+pub use fen::FenError;
+pub use outcome::Outcome;
+pub use params::{EvalParams, DEFAULT_PARAMS};
+pub use pawns::PawnTable;
+pub use pgn::{Game, GameNode};
+pub use piece::{Piece, PieceType, PIECE_TYPES};
 pub use position::*;
-
-fn negamax(chess: Chess, mut alpha: i32, beta: i32, depth: i32, turn: Color) -> i32 {
-    if depth == 0 {
-        return chess.evaluate(turn);
-    }
-
-    let legal_moves = chess.gen_legal_moves();
-    if legal_moves.is_empty() {
-        if !chess.is_check() {
-            return 0;
-        } else {
-            return (i32::MIN / 2) - depth;
-        }
-    }
-
-    let ordered_moves = chess.sort_moves(legal_moves);
-
-    for (_, legal_move) in ordered_moves {
-        let eval = !negamax(
-            chess.set_move(legal_move).invert_turn(),
-            !beta,
-            !alpha,
-            depth - 1,
-            !turn,
-        );
-
-        if eval >= beta {
-            return beta;
-        }
-
-        alpha = max(eval, alpha);
-    }
-    alpha
-}
+pub use search::{SearchLimits, SearchOptions, SearchResult, Searcher, INFINITY, MATE, MAX_PLY};
+pub use texel::{find_k, tunable_values, TuningEntry, TuningSet};
+pub use tt::{score_from_tt, Bound, TranspositionTable, DEFAULT_HASH_MB};
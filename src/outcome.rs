@@ -0,0 +1,66 @@
+use crate::{
+    Chess,
+    Color::{self, *},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Checkmate(Color),
+    Stalemate,
+    ThreefoldRepetition,
+    FivefoldRepetition,
+    FiftyMoves,
+    SeventyFiveMoves,
+    InsufficientMaterial,
+}
+
+impl Outcome {
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            Outcome::Checkmate(winner) => Some(winner),
+            _ => None,
+        }
+    }
+
+    // The result as written in PGN.
+    pub fn result(self) -> &'static str {
+        match self.winner() {
+            Some(White) => "1-0",
+            Some(Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+impl Chess {
+    // Draws that the rules end automatically are reported before the ones a player
+    // has to claim.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if self.gen_legal_moves().is_empty() {
+            return Some(if self.is_check() {
+                Outcome::Checkmate(!self.turn)
+            } else {
+                Outcome::Stalemate
+            });
+        }
+
+        let repetitions = self.repetitions();
+        if repetitions >= 4 {
+            Some(Outcome::FivefoldRepetition)
+        } else if self.halfmove_clock >= 150 {
+            Some(Outcome::SeventyFiveMoves)
+        } else if self.is_insufficient_material() {
+            Some(Outcome::InsufficientMaterial)
+        } else if repetitions >= 2 {
+            Some(Outcome::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(Outcome::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.outcome().is_some()
+    }
+}
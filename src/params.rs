@@ -0,0 +1,466 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::Path,
+    slice,
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::{
+    board::Color::{self, *},
+    eval::Score,
+    piece::PieceType,
+};
+
+// Every weight of the evaluation, so that they can be changed without recompiling.
+// Arrays of six are indexed by `PieceType`, arrays of eight by the rank from the point
+// of view of the owner.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvalParams {
+    pub material: [Score; 6],
+    // From White's point of view, a8 first like the bitboards.
+    pub psqt: [[Score; 64]; 6],
+
+    pub doubled: Score,
+    pub isolated: Score,
+    pub backward: Score,
+    pub connected: [Score; 8],
+    pub passed: [Score; 8],
+    pub passed_free_path: [Score; 8],
+    pub passed_safe_path: [Score; 8],
+
+    pub king_attack: Score,
+    pub shield_advanced: Score,
+    pub shield_missing: Score,
+    pub open_file: Score,
+
+    pub mobility: [Score; 6],
+    pub rook_open_file: Score,
+    pub rook_semi_open_file: Score,
+    pub rook_seventh_rank: Score,
+    pub bishop_pair: Score,
+    pub knight_outpost: Score,
+    pub trapped_rook: Score,
+    pub trapped_bishop: Score,
+}
+
+pub static DEFAULT_PARAMS: EvalParams = EvalParams {
+    material: MATERIAL,
+    psqt: gen_psqt(
+        [MG_KING, MG_QUEEN, MG_ROOK, MG_BISHOP, MG_KNIGHT, MG_PAWN],
+        [EG_KING, EG_QUEEN, EG_ROOK, EG_BISHOP, EG_KNIGHT, EG_PAWN],
+    ),
+
+    doubled: Score::new(-10, -20),
+    isolated: Score::new(-8, -12),
+    backward: Score::new(-6, -10),
+    connected: [
+        Score::new(0, 0),
+        Score::new(3, 1),
+        Score::new(5, 3),
+        Score::new(8, 6),
+        Score::new(14, 14),
+        Score::new(24, 30),
+        Score::new(40, 55),
+        Score::new(0, 0),
+    ],
+    passed: [
+        Score::new(0, 0),
+        Score::new(0, 5),
+        Score::new(0, 8),
+        Score::new(5, 15),
+        Score::new(15, 30),
+        Score::new(30, 55),
+        Score::new(50, 90),
+        Score::new(0, 0),
+    ],
+    passed_free_path: [
+        Score::new(0, 0),
+        Score::new(0, 0),
+        Score::new(0, 2),
+        Score::new(2, 6),
+        Score::new(5, 12),
+        Score::new(10, 25),
+        Score::new(20, 40),
+        Score::new(0, 0),
+    ],
+    passed_safe_path: [
+        Score::new(0, 0),
+        Score::new(0, 0),
+        Score::new(0, 2),
+        Score::new(0, 5),
+        Score::new(3, 10),
+        Score::new(6, 20),
+        Score::new(12, 35),
+        Score::new(0, 0),
+    ],
+
+    king_attack: Score::new(25, 0),
+    shield_advanced: Score::new(-10, 0),
+    shield_missing: Score::new(-25, 0),
+    open_file: Score::new(-15, 0),
+
+    mobility: [
+        Score::new(0, 0),
+        Score::new(1, 2),
+        Score::new(2, 4),
+        Score::new(5, 5),
+        Score::new(4, 4),
+        Score::new(0, 0),
+    ],
+    rook_open_file: Score::new(25, 10),
+    rook_semi_open_file: Score::new(12, 6),
+    rook_seventh_rank: Score::new(15, 25),
+    bishop_pair: Score::new(30, 50),
+    knight_outpost: Score::new(20, 10),
+    trapped_rook: Score::new(-40, -10),
+    trapped_bishop: Score::new(-80, -80),
+};
+
+const MATERIAL: [Score; 6] = [
+    Score::new(0, 0),
+    Score::new(1025, 936),
+    Score::new(477, 512),
+    Score::new(365, 297),
+    Score::new(337, 281),
+    Score::new(82, 94),
+];
+
+#[rustfmt::skip]
+const MG_KING: [i32; 64] = [
+    -65,  23,  16, -15, -56, -34,   2,  13,
+     29,  -1, -20,  -7,  -8,  -4, -38, -29,
+     -9,  24,   2, -16, -20,   6,  22, -22,
+    -17, -20, -12, -27, -30, -25, -14, -36,
+    -49,  -1, -27, -39, -46, -44, -33, -51,
+    -14, -14, -22, -46, -44, -30, -15, -27,
+      1,   7,  -8, -64, -43, -16,   9,   8,
+    -15,  36,  12, -54,   8, -28,  24,  14,
+];
+
+#[rustfmt::skip]
+const EG_KING: [i32; 64] = [
+    -74, -35, -18, -18, -11,  15,   4, -17,
+    -12,  17,  14,  17,  17,  38,  23,  11,
+     10,  17,  23,  15,  20,  45,  44,  13,
+     -8,  22,  24,  27,  26,  33,  26,   3,
+    -18,  -4,  21,  24,  27,  23,   9, -11,
+    -19,  -3,  11,  21,  23,  16,   7,  -9,
+    -27, -11,   4,  13,  14,   4,  -5, -17,
+    -53, -34, -21, -11, -28, -14, -24, -43,
+];
+
+#[rustfmt::skip]
+const MG_QUEEN: [i32; 64] = [
+    -28,   0,  29,  12,  59,  44,  43,  45,
+    -24, -39,  -5,   1, -16,  57,  28,  54,
+    -13, -17,   7,   8,  29,  56,  47,  57,
+    -27, -27, -16, -16,  -1,  17,  -2,   1,
+     -9, -26,  -9, -10,  -2,  -4,   3,  -3,
+    -14,   2, -11,  -2,  -5,   2,  14,   5,
+    -35,  -8,  11,   2,   8,  15,  -3,   1,
+     -1, -18,  -9,  10, -15, -25, -31, -50,
+];
+
+#[rustfmt::skip]
+const EG_QUEEN: [i32; 64] = [
+     -9,  22,  22,  27,  27,  19,  10,  20,
+    -17,  20,  32,  41,  58,  25,  30,   0,
+    -20,   6,   9,  49,  47,  35,  19,   9,
+      3,  22,  24,  45,  57,  40,  57,  36,
+    -18,  28,  19,  47,  31,  34,  39,  23,
+    -16, -27,  15,   6,   9,  17,  10,   5,
+    -22, -23, -30, -16, -16, -23, -36, -32,
+    -33, -28, -22, -43,  -5, -32, -20, -41,
+];
+
+#[rustfmt::skip]
+const MG_ROOK: [i32; 64] = [
+     32,  42,  32,  51,  63,   9,  31,  43,
+     27,  32,  58,  62,  80,  67,  26,  44,
+     -5,  19,  26,  36,  17,  45,  61,  16,
+    -24, -11,   7,  26,  24,  35,  -8, -20,
+    -36, -26, -12,  -1,   9,  -7,   6, -23,
+    -45, -25, -16, -17,   3,   0,  -5, -33,
+    -44, -16, -20,  -9,  -1,  11,  -6, -71,
+    -19, -13,   1,  17,  16,   7, -37, -26,
+];
+
+#[rustfmt::skip]
+const EG_ROOK: [i32; 64] = [
+     13,  10,  18,  15,  12,  12,   8,   5,
+     11,  13,  13,  11,  -3,   3,   8,   3,
+      7,   7,   7,   5,   4,  -3,  -5,  -3,
+      4,   3,  13,   1,   2,   1,  -1,   2,
+      3,   5,   8,   4,  -5,  -6,  -8, -11,
+     -4,   0,  -5,  -1,  -7, -12,  -8, -16,
+     -6,  -6,   0,   2,  -9,  -9, -11,  -3,
+     -9,   2,   3,  -1,  -5, -13,   4, -20,
+];
+
+#[rustfmt::skip]
+const MG_BISHOP: [i32; 64] = [
+    -29,   4, -82, -37, -25, -42,   7,  -8,
+    -26,  16, -18, -13,  30,  59,  18, -47,
+    -16,  37,  43,  40,  35,  50,  37,  -2,
+     -4,   5,  19,  50,  37,  37,   7,  -2,
+     -6,  13,  13,  26,  34,  12,  10,   4,
+      0,  15,  15,  15,  14,  27,  18,  10,
+      4,  15,  16,   0,   7,  21,  33,   1,
+    -33,  -3, -14, -21, -13, -12, -39, -21,
+];
+
+#[rustfmt::skip]
+const EG_BISHOP: [i32; 64] = [
+    -14, -21, -11,  -8,  -7,  -9, -17, -24,
+     -8,  -4,   7, -12,  -3, -13,  -4, -14,
+      2,  -8,   0,  -1,  -2,   6,   0,   4,
+     -3,   9,  12,   9,  14,  10,   3,   2,
+     -6,   3,  13,  19,   7,  10,  -3,  -9,
+    -12,  -3,   8,  10,  13,   3,  -7, -15,
+    -14, -18,  -7,  -1,   4,  -9, -15, -27,
+    -23,  -9, -23,  -5,  -9, -16,  -5, -17,
+];
+
+#[rustfmt::skip]
+const MG_KNIGHT: [i32; 64] = [
+   -167, -89, -34, -49,  61, -97, -15,-107,
+    -73, -41,  72,  36,  23,  62,   7, -17,
+    -47,  60,  37,  65,  84, 129,  73,  44,
+     -9,  17,  19,  53,  37,  69,  18,  22,
+    -13,   4,  16,  13,  28,  19,  21,  -8,
+    -23,  -9,  12,  10,  19,  17,  25, -16,
+    -29, -53, -12,  -3,  -1,  18, -14, -19,
+   -105, -21, -58, -33, -17, -28, -19, -23,
+];
+
+#[rustfmt::skip]
+const EG_KNIGHT: [i32; 64] = [
+    -58, -38, -13, -28, -31, -27, -63, -99,
+    -25,  -8, -25,  -2,  -9, -25, -24, -52,
+    -24, -20,  10,   9,  -1,  -9, -19, -41,
+    -17,   3,  22,  22,  22,  11,   8, -18,
+    -18,  -6,  16,  25,  16,  17,   4, -18,
+    -23,  -3,  -1,  15,  10,  -3, -20, -22,
+    -42, -20, -10,  -5,  -2, -20, -23, -44,
+    -29, -51, -23, -15, -22, -18, -50, -64,
+];
+
+#[rustfmt::skip]
+const MG_PAWN: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     98, 134,  61,  95,  68, 126,  34, -11,
+     -6,   7,  26,  31,  65,  56,  25, -20,
+    -14,  13,   6,  21,  23,  12,  17, -23,
+    -27,  -2,  -5,  12,  17,   6,  10, -25,
+    -26,  -4,  -4, -10,   3,   3,  33, -12,
+    -35,  -1, -20, -23, -15,  24,  38, -22,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const EG_PAWN: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+    178, 173, 158, 134, 147, 132, 165, 187,
+     94, 100,  85,  67,  56,  53,  82,  84,
+     32,  24,  13,   5,  -2,   4,  17,  17,
+     13,   9,  -3,  -7,  -7,  -8,   3,  -1,
+      4,   7,  -6,   1,   0,  -5,  -1,  -8,
+     13,   8,   8,  10,  13,   0,   2,  -7,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+const fn gen_psqt(mg: [[i32; 64]; 6], eg: [[i32; 64]; 6]) -> [[Score; 64]; 6] {
+    let mut psqt = [[Score::new(0, 0); 64]; 6];
+    let mut piece_type = 0;
+    while piece_type < 6 {
+        let mut sq = 0;
+        while sq < 64 {
+            psqt[piece_type][sq] = Score::new(mg[piece_type][sq], eg[piece_type][sq]);
+            sq += 1;
+        }
+        piece_type += 1;
+    }
+    psqt
+}
+
+// The names of the parameters in the file, in the order of `EvalParams::field`.
+pub const FIELDS: [&str; 26] = [
+    "material",
+    "psqt_king",
+    "psqt_queen",
+    "psqt_rook",
+    "psqt_bishop",
+    "psqt_knight",
+    "psqt_pawn",
+    "doubled",
+    "isolated",
+    "backward",
+    "connected",
+    "passed",
+    "passed_free_path",
+    "passed_safe_path",
+    "king_attack",
+    "shield_advanced",
+    "shield_missing",
+    "open_file",
+    "mobility",
+    "rook_open_file",
+    "rook_semi_open_file",
+    "rook_seventh_rank",
+    "bishop_pair",
+    "knight_outpost",
+    "trapped_rook",
+    "trapped_bishop",
+];
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        DEFAULT_PARAMS.clone()
+    }
+}
+
+impl EvalParams {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    // Black looks the tables up with the square mirrored vertically.
+    pub fn psqt(&self, piece_type: PieceType, color: Color, sq: usize) -> Score {
+        let sq = match color {
+            White => sq,
+            Black => sq ^ 56,
+        };
+        self.psqt[piece_type as usize][sq]
+    }
+
+    // Every parameter under its name in the file, for loading, saving and tuning.
+    pub fn fields(&self) -> Vec<(&'static str, &[Score])> {
+        FIELDS
+            .iter()
+            .enumerate()
+            .map(|(field, &name)| (name, self.field(field)))
+            .collect()
+    }
+
+    // The scores of the parameter named `FIELDS[field]`.
+    pub fn field(&self, field: usize) -> &[Score] {
+        match field {
+            0 => &self.material,
+            1..=6 => &self.psqt[field - 1],
+            7 => slice::from_ref(&self.doubled),
+            8 => slice::from_ref(&self.isolated),
+            9 => slice::from_ref(&self.backward),
+            10 => &self.connected,
+            11 => &self.passed,
+            12 => &self.passed_free_path,
+            13 => &self.passed_safe_path,
+            14 => slice::from_ref(&self.king_attack),
+            15 => slice::from_ref(&self.shield_advanced),
+            16 => slice::from_ref(&self.shield_missing),
+            17 => slice::from_ref(&self.open_file),
+            18 => &self.mobility,
+            19 => slice::from_ref(&self.rook_open_file),
+            20 => slice::from_ref(&self.rook_semi_open_file),
+            21 => slice::from_ref(&self.rook_seventh_rank),
+            22 => slice::from_ref(&self.bishop_pair),
+            23 => slice::from_ref(&self.knight_outpost),
+            24 => slice::from_ref(&self.trapped_rook),
+            25 => slice::from_ref(&self.trapped_bishop),
+            _ => panic!("no parameter {}", field),
+        }
+    }
+
+    pub fn field_mut(&mut self, field: usize) -> &mut [Score] {
+        match field {
+            0 => &mut self.material,
+            1..=6 => &mut self.psqt[field - 1],
+            7 => slice::from_mut(&mut self.doubled),
+            8 => slice::from_mut(&mut self.isolated),
+            9 => slice::from_mut(&mut self.backward),
+            10 => &mut self.connected,
+            11 => &mut self.passed,
+            12 => &mut self.passed_free_path,
+            13 => &mut self.passed_safe_path,
+            14 => slice::from_mut(&mut self.king_attack),
+            15 => slice::from_mut(&mut self.shield_advanced),
+            16 => slice::from_mut(&mut self.shield_missing),
+            17 => slice::from_mut(&mut self.open_file),
+            18 => &mut self.mobility,
+            19 => slice::from_mut(&mut self.rook_open_file),
+            20 => slice::from_mut(&mut self.rook_semi_open_file),
+            21 => slice::from_mut(&mut self.rook_seventh_rank),
+            22 => slice::from_mut(&mut self.bishop_pair),
+            23 => slice::from_mut(&mut self.knight_outpost),
+            24 => slice::from_mut(&mut self.trapped_rook),
+            25 => slice::from_mut(&mut self.trapped_bishop),
+            _ => panic!("no parameter {}", field),
+        }
+    }
+}
+
+// One parameter per line: its name, then the middlegame and the endgame value of each
+// of its scores.
+impl Display for EvalParams {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        for (name, scores) in self.fields() {
+            write!(f, "{}", name)?;
+            for score in scores.iter() {
+                write!(f, " {} {}", score.mg, score.eg)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Parameters missing from the text keep their default value.
+impl FromStr for EvalParams {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut params = Self::default();
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens = line.split_ascii_whitespace();
+            let name = tokens.next().unwrap_or_default();
+            let scores = match FIELDS.iter().position(|&field| field == name) {
+                Some(field) => params.field_mut(field),
+                None => bail!("unknown parameter: {}", name),
+            };
+
+            let values = tokens
+                .map(|token| {
+                    token
+                        .parse()
+                        .map_err(|_| anyhow!("error parsing {}: {}", name, token))
+                })
+                .collect::<Result<Vec<i32>>>()?;
+            if values.len() != scores.len() * 2 {
+                bail!(
+                    "{} takes {} values, not {}",
+                    name,
+                    scores.len() * 2,
+                    values.len()
+                )
+            }
+
+            for (score, value) in scores.iter_mut().zip(values.chunks(2)) {
+                *score = Score::new(value[0], value[1])
+            }
+        }
+
+        Ok(params)
+    }
+}
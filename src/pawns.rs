@@ -0,0 +1,164 @@
+use crate::{
+    bitboard::*,
+    board::Color::{self, *},
+    eval::{attacks_by, Score},
+    params::EvalParams,
+    piece::PieceType::*,
+    Chess,
+};
+
+const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+// The part of the pawn evaluation that depends on the pawns alone.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct PawnEntry {
+    pub key: u64,
+    pub score: [Score; 2],
+    pub passed: [Bitboard; 2],
+}
+
+impl PawnEntry {
+    pub fn new(chess: &Chess, params: &EvalParams) -> Self {
+        let mut entry = Self {
+            key: chess.pawn_hash,
+            ..Self::default()
+        };
+
+        for &color in &[White, Black] {
+            let (score, passed) = evaluate_structure(chess, params, color);
+            entry.score[color as usize] = score;
+            entry.passed[color as usize] = passed;
+        }
+
+        entry
+    }
+}
+
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PawnTable {
+    pub fn new() -> Self {
+        Self {
+            entries: vec![None; PAWN_TABLE_SIZE],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None)
+    }
+
+    // The entries depend on `params`, so the table must be cleared when they change.
+    pub fn probe(&mut self, chess: &Chess, params: &EvalParams) -> PawnEntry {
+        let index = (chess.pawn_hash % PAWN_TABLE_SIZE as u64) as usize;
+        match self.entries[index] {
+            Some(entry) if entry.key == chess.pawn_hash => entry,
+            _ => {
+                let entry = PawnEntry::new(chess, params);
+                self.entries[index] = Some(entry);
+                entry
+            }
+        }
+    }
+}
+
+pub fn relative_rank(color: Color, sq: usize) -> usize {
+    match color {
+        White => 7 - sq / 8,
+        Black => sq / 8,
+    }
+}
+
+pub fn pawn_attacks_all(color: Color, pawns: Bitboard) -> Bitboard {
+    squares(pawns).fold(EMPTY, |attacks, sq| attacks | pawn_attacks(color, sq))
+}
+
+fn evaluate_structure(chess: &Chess, params: &EvalParams, color: Color) -> (Score, Bitboard) {
+    let pawns = chess.pieces_color(Pawn, color);
+    let enemy_pawns = chess.pieces_color(Pawn, !color);
+    let enemy_attacks = pawn_attacks_all(!color, enemy_pawns);
+
+    let mut score = Score::default();
+    let mut passed = EMPTY;
+
+    for file in &FILES {
+        let count = (pawns & file).count_ones() as i32;
+        if count > 1 {
+            score += params.doubled * (count - 1)
+        }
+    }
+
+    for sq in squares(pawns) {
+        let (row, col) = (sq / 8, sq % 8);
+        let rank = relative_rank(color, sq);
+        let ahead = forward_ranks(color, row);
+        let neighbours = pawns & adjacent_files(col);
+
+        // defended by a pawn, or side by side with one
+        let supporters = neighbours & pawn_attacks(!color, sq);
+        let phalanx = neighbours & (0xFF << (row * 8));
+        if supporters | phalanx != EMPTY {
+            score += params.connected[rank]
+        }
+
+        if neighbours == EMPTY {
+            score += params.isolated
+        } else if neighbours & !ahead == EMPTY {
+            // every neighbour is already ahead, so no pawn can come to help and
+            // advancing walks into an enemy pawn capture
+            let stop = match color {
+                White => sq - 8,
+                Black => sq + 8,
+            };
+            if square_bb(stop) & enemy_attacks != EMPTY {
+                score += params.backward
+            }
+        }
+
+        let front_span = (FILES[col] | adjacent_files(col)) & ahead;
+        if front_span & enemy_pawns == EMPTY && FILES[col] & ahead & pawns == EMPTY {
+            score += params.passed[rank];
+            passed |= square_bb(sq)
+        }
+    }
+
+    (score, passed)
+}
+
+// Passed pawns are worth more when nothing stands in their way and the enemy does
+// not control the squares in front of them.
+pub fn evaluate_passed(
+    chess: &Chess,
+    params: &EvalParams,
+    color: Color,
+    passed: Bitboard,
+) -> Score {
+    let mut score = Score::default();
+    if passed == EMPTY {
+        return score;
+    }
+
+    let occupied = chess.occupied();
+    let enemy_attacks = attacks_by(chess, !color);
+
+    for sq in squares(passed) {
+        let rank = relative_rank(color, sq);
+        let path = FILES[sq % 8] & forward_ranks(color, sq / 8);
+
+        if path & occupied == EMPTY {
+            score += params.passed_free_path[rank];
+            if path & enemy_attacks == EMPTY {
+                score += params.passed_safe_path[rank]
+            }
+        }
+    }
+
+    score
+}
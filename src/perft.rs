@@ -0,0 +1,44 @@
+use crate::{position::Move, Chess};
+
+impl Chess {
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut chess = self.clone();
+        perft(&mut chess, depth)
+    }
+
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut chess = self.clone();
+        self.gen_legal_moves()
+            .into_iter()
+            .map(|r#move| {
+                let undo = chess.make_move(r#move);
+                let nodes = perft(&mut chess, depth - 1);
+                chess.unmake_move(r#move, undo);
+                (r#move, nodes)
+            })
+            .collect()
+    }
+}
+
+fn perft(chess: &mut Chess, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let legal_moves = chess.gen_legal_moves();
+    if depth == 1 {
+        return legal_moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for r#move in legal_moves {
+        let undo = chess.make_move(r#move);
+        nodes += perft(chess, depth - 1);
+        chess.unmake_move(r#move, undo);
+    }
+    nodes
+}
@@ -0,0 +1,555 @@
+use anyhow::{bail, Result};
+
+use crate::{position::Move, Chess, Color::White};
+
+// The export format wants lines shorter than 80 characters.
+const LINE_WIDTH: usize = 79;
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+#[derive(Clone, Debug)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub start: Chess,
+    pub comment: Option<String>,
+    pub moves: Vec<GameNode>,
+    pub result: String,
+}
+
+// A move of a line, together with the lines that could have been played instead of it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameNode {
+    pub r#move: Move,
+    pub starting_comment: Option<String>,
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variations: Vec<Vec<GameNode>>,
+}
+
+impl GameNode {
+    pub fn new(r#move: Move) -> Self {
+        Self {
+            r#move,
+            starting_comment: None,
+            nags: Vec::new(),
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        Self {
+            tags: SEVEN_TAG_ROSTER
+                .iter()
+                .map(|&(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            start: Chess::new(),
+            comment: None,
+            moves: Vec::new(),
+            result: "*".to_string(),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn mainline(&self) -> Vec<Move> {
+        self.moves.iter().map(|node| node.r#move).collect()
+    }
+
+    pub fn end_position(&self) -> Chess {
+        let mut chess = self.start.clone();
+        for node in &self.moves {
+            chess.set(node.r#move)
+        }
+        chess
+    }
+
+    pub fn parse_all(pgn: &str) -> Result<Vec<Game>> {
+        let mut games = Vec::new();
+        let mut parser = Parser::new();
+
+        for token in tokenize(pgn)? {
+            if let Some(game) = parser.push(token)? {
+                games.push(game)
+            }
+        }
+        if let Some(game) = parser.finish()? {
+            games.push(game)
+        }
+
+        Ok(games)
+    }
+
+    pub fn to_pgn(&self) -> Result<String> {
+        let mut pgn = String::new();
+
+        for &(name, default) in &SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => &self.result,
+                _ => self.tag(name).unwrap_or(default),
+            };
+            pgn.push_str(&format_tag(name, value))
+        }
+        // the setup tags always describe `start`, whatever the tags say
+        let fen = self.start.to_fen();
+        if fen != Chess::new().to_fen() {
+            pgn.push_str(&format_tag("SetUp", "1"));
+            pgn.push_str(&format_tag("FEN", &fen))
+        }
+        for (name, value) in &self.tags {
+            if !SEVEN_TAG_ROSTER.iter().any(|&(roster, _)| roster == name)
+                && name != "SetUp"
+                && name != "FEN"
+            {
+                pgn.push_str(&format_tag(name, value))
+            }
+        }
+        pgn.push('\n');
+
+        let mut writer = Writer::default();
+        if let Some(comment) = &self.comment {
+            writer.comment(comment)
+        }
+        write_line(&mut writer, &self.start, &self.moves)?;
+        writer.token(&self.result);
+
+        pgn.push_str(&writer.finish());
+        pgn.push('\n');
+
+        Ok(pgn)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(String),
+    San(String),
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = pgn.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\n' => {
+                line_start = true;
+                continue;
+            }
+            _ if chr.is_whitespace() => continue,
+            // escaped lines are reserved for other programs
+            '%' if line_start => {
+                chars.by_ref().find(|&chr| chr == '\n');
+                continue;
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&chr| chr != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+                continue;
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(chr) => comment.push(chr),
+                        None => bail!("unterminated comment"),
+                    }
+                }
+                let words: Vec<&str> = comment.split_whitespace().collect();
+                tokens.push(Token::Comment(words.join(" ")));
+            }
+            '[' => {
+                let mut tag = String::new();
+                let mut in_string = false;
+                loop {
+                    match chars.next() {
+                        Some(']') if !in_string => break,
+                        Some('\\') if in_string => {
+                            tag.push('\\');
+                            tag.extend(chars.next())
+                        }
+                        Some(chr) => {
+                            in_string ^= chr == '"';
+                            tag.push(chr)
+                        }
+                        None => bail!("unterminated tag"),
+                    }
+                }
+                tokens.push(parse_tag(&tag)?)
+            }
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '$' => {
+                let mut nag = String::new();
+                while let Some(&chr) = chars.peek().filter(|chr| chr.is_ascii_digit()) {
+                    nag.push(chr);
+                    chars.next();
+                }
+                match nag.parse() {
+                    Ok(nag) => tokens.push(Token::Nag(nag)),
+                    Err(_) => bail!("error parsing NAG: ${}", nag),
+                }
+            }
+            _ => {
+                let mut symbol = chr.to_string();
+                while let Some(&chr) = chars
+                    .peek()
+                    .filter(|chr| !chr.is_whitespace() && !"{}()[];$".contains(**chr))
+                {
+                    symbol.push(chr);
+                    chars.next();
+                }
+                push_symbol(&mut tokens, &symbol)?
+            }
+        }
+        line_start = false;
+    }
+
+    Ok(tokens)
+}
+
+fn parse_tag(tag: &str) -> Result<Token> {
+    let (name, value) = match tag.trim().split_once(char::is_whitespace) {
+        Some((name, value)) => (name, value.trim()),
+        None => bail!("error parsing tag: [{}]", tag),
+    };
+
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        bail!("error parsing tag: [{}]", tag)
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = value[1..value.len() - 1].chars();
+    while let Some(chr) = chars.next() {
+        match chr {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(chr),
+        }
+    }
+
+    Ok(Token::Tag(name.to_string(), unescaped))
+}
+
+fn push_symbol(tokens: &mut Vec<Token>, symbol: &str) -> Result<()> {
+    if let "1-0" | "0-1" | "1/2-1/2" | "*" = symbol {
+        tokens.push(Token::Result(symbol.to_string()));
+        return Ok(());
+    }
+
+    // move numbers may be glued to the move, as in "1.e4" or "12...Nf6", or stand
+    // alone without their dots
+    let number_len = symbol
+        .find(|chr: char| !chr.is_ascii_digit())
+        .unwrap_or(symbol.len());
+    let symbol = if symbol[number_len..].starts_with('.') {
+        symbol[number_len..].trim_start_matches('.')
+    } else if number_len == symbol.len() {
+        ""
+    } else {
+        symbol
+    };
+
+    let glyphs = symbol.trim_start_matches(|chr| !"!?".contains(chr));
+    let san = &symbol[..symbol.len() - glyphs.len()];
+    if !san.is_empty() {
+        tokens.push(Token::San(san.to_string()))
+    }
+
+    let nag = match glyphs {
+        "" => return Ok(()),
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        "?!" => 6,
+        _ => bail!("error parsing annotation: {}", glyphs),
+    };
+    tokens.push(Token::Nag(nag));
+
+    Ok(())
+}
+
+struct Frame {
+    moves: Vec<GameNode>,
+    // positions before and after the last move of the line
+    before: Chess,
+    after: Chess,
+    starting_comment: Option<String>,
+}
+
+impl Frame {
+    fn new(chess: Chess) -> Self {
+        Self {
+            moves: Vec::new(),
+            before: chess.clone(),
+            after: chess,
+            starting_comment: None,
+        }
+    }
+}
+
+struct Parser {
+    tags: Vec<(String, String)>,
+    comment: Option<String>,
+    frames: Vec<Frame>,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Self {
+            tags: Vec::new(),
+            comment: None,
+            frames: vec![Frame::new(Chess::new())],
+        }
+    }
+
+    fn has_movetext(&self) -> bool {
+        self.comment.is_some() || !self.frames[0].moves.is_empty()
+    }
+
+    fn push(&mut self, token: Token) -> Result<Option<Game>> {
+        let is_mainline = self.frames.len() == 1;
+        let frame = self.frames.last_mut().unwrap();
+
+        match token {
+            Token::Tag(name, value) => {
+                let game = if self.has_movetext() {
+                    self.finish()?
+                } else {
+                    None
+                };
+
+                if name == "FEN" {
+                    let chess = Chess::from_fen(&value)?;
+                    self.frames = vec![Frame::new(chess)];
+                }
+                self.tags.push((name, value));
+
+                return Ok(game);
+            }
+            Token::Comment(comment) => match frame.moves.last_mut() {
+                Some(node) => append_comment(&mut node.comment, comment),
+                None if is_mainline => append_comment(&mut self.comment, comment),
+                None => append_comment(&mut frame.starting_comment, comment),
+            },
+            Token::Nag(nag) => match frame.moves.last_mut() {
+                Some(node) => node.nags.push(nag),
+                None => bail!("NAG ${} before any move", nag),
+            },
+            Token::Open => {
+                if frame.moves.is_empty() {
+                    bail!("variation before any move")
+                }
+                let chess = frame.before.clone();
+                self.frames.push(Frame::new(chess));
+            }
+            Token::Close => {
+                if is_mainline {
+                    bail!("unmatched closing parenthesis")
+                }
+                let variation = self.frames.pop().unwrap().moves;
+                let frame = self.frames.last_mut().unwrap();
+                if !variation.is_empty() {
+                    frame.moves.last_mut().unwrap().variations.push(variation)
+                }
+            }
+            Token::Result(result) => {
+                self.tags.retain(|(name, _)| name != "Result");
+                self.tags.push(("Result".to_string(), result));
+                return self.finish();
+            }
+            Token::San(san) => {
+                let r#move = match frame.after.parse_san(&san) {
+                    Ok(r#move) => r#move,
+                    Err(err) => bail!("{} after {}", err, frame.after.to_fen()),
+                };
+
+                let mut node = GameNode::new(r#move);
+                node.starting_comment = frame.starting_comment.take();
+                frame.moves.push(node);
+                frame.before = frame.after.clone();
+                frame.after.set(r#move);
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn finish(&mut self) -> Result<Option<Game>> {
+        if self.frames.len() != 1 {
+            bail!("unterminated variation")
+        }
+
+        let parser = std::mem::replace(self, Parser::new());
+        if parser.tags.is_empty() && !parser.has_movetext() {
+            return Ok(None);
+        }
+
+        let frame = parser.frames.into_iter().next().unwrap();
+        let mut game = Game {
+            tags: parser.tags,
+            start: Chess::new(),
+            comment: parser.comment,
+            moves: frame.moves,
+            result: "*".to_string(),
+        };
+        if let Some(fen) = game.tag("FEN") {
+            game.start = Chess::from_fen(fen)?
+        }
+        if let Some(result) = game.tag("Result") {
+            game.result = result.to_string()
+        }
+
+        Ok(Some(game))
+    }
+}
+
+fn append_comment(old: &mut Option<String>, comment: String) {
+    match old {
+        Some(old) => {
+            old.push(' ');
+            old.push_str(&comment)
+        }
+        None => *old = Some(comment),
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, value)
+}
+
+fn write_line(writer: &mut Writer, chess: &Chess, moves: &[GameNode]) -> Result<()> {
+    let mut chess = chess.clone();
+    let mut needs_number = true;
+
+    for node in moves {
+        if let Some(comment) = &node.starting_comment {
+            writer.comment(comment);
+            needs_number = true
+        }
+
+        let san = chess.move_to_san(node.r#move)?;
+        if chess.turn == White {
+            writer.token(&format!("{}. {}", chess.fullmove_number, san))
+        } else if needs_number {
+            writer.token(&format!("{}... {}", chess.fullmove_number, san))
+        } else {
+            writer.token(&san)
+        }
+        needs_number = false;
+
+        for nag in &node.nags {
+            writer.token(&format!("${}", nag))
+        }
+
+        if let Some(comment) = &node.comment {
+            writer.comment(comment);
+            needs_number = true
+        }
+
+        for variation in &node.variations {
+            writer.open();
+            write_line(writer, &chess, variation)?;
+            writer.close();
+            needs_number = true
+        }
+
+        chess.set(node.r#move)
+    }
+
+    Ok(())
+}
+
+// Parentheses are glued to the tokens next to them and move numbers to their move,
+// so lines are only broken where a space would be.
+#[derive(Default)]
+struct Writer {
+    tokens: Vec<String>,
+    after_open: bool,
+}
+
+impl Writer {
+    fn token(&mut self, token: &str) {
+        if self.after_open {
+            self.after_open = false;
+            self.tokens.push(format!("({}", token))
+        } else {
+            self.tokens.push(token.to_string())
+        }
+    }
+
+    fn comment(&mut self, comment: &str) {
+        for word in format!("{{{}}}", comment).split(' ') {
+            self.token(word)
+        }
+    }
+
+    fn open(&mut self) {
+        self.after_open = true
+    }
+
+    fn close(&mut self) {
+        match self.tokens.last_mut() {
+            Some(token) if !self.after_open => token.push(')'),
+            _ => self.token("()"),
+        }
+        self.after_open = false
+    }
+
+    fn finish(self) -> String {
+        let mut out = String::new();
+        let mut line_len = 0;
+
+        for token in self.tokens {
+            if line_len > 0 && line_len + 1 + token.len() > LINE_WIDTH {
+                out.push('\n');
+                line_len = 0
+            } else if line_len > 0 {
+                out.push(' ');
+                line_len += 1
+            }
+            out.push_str(&token);
+            line_len += token.len()
+        }
+
+        out
+    }
+}
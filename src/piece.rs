@@ -2,87 +2,12 @@ use std::fmt::Display;
 
 use crate::position::{Move, Position};
 use crate::{
-    board::{
-        Color::{self, *},
-        Square,
-    },
+    bitboard::*,
+    board::Color::{self, *},
     Chess,
 };
 
-const PAWN_SQUARE_TABLE_WHITE: [i32; 64] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 50, 50, 50, 50, 50, 50, 50, 50, 10, 10, 20, 30, 30, 20, 10, 10, 5, 5,
-    10, 25, 25, 10, 5, 5, 0, 0, 0, 20, 20, 0, 0, 0, 5, -5, -10, 0, 0, -10, -5, 5, 5, 10, 10, -20,
-    -20, 10, 10, 5, 0, 0, 0, 0, 0, 0, 0, 0,
-];
-
-const PAWN_SQUARE_TABLE_BLACK: [i32; 64] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 5, 10, 10, -20, -20, 10, 10, 5, 5, -5, -10, 0, 0, -10, -5, 5, 0, 0, 0,
-    20, 20, 0, 0, 0, 5, 5, 10, 25, 25, 10, 5, 5, 10, 10, 20, 30, 30, 20, 10, 10, 50, 50, 50, 50,
-    50, 50, 50, 50, 0, 0, 0, 0, 0, 0, 0, 0,
-];
-
-const KNIGHT_SQUARE_TABLE_WHITE: [i32; 64] = [
-    -50, -40, -30, -30, -30, -30, -40, -50, -40, -20, 0, 0, 0, 0, -20, -40, -30, 0, 10, 15, 15, 10,
-    0, -30, -30, 5, 15, 20, 20, 15, 5, -30, -30, 0, 15, 20, 20, 15, 0, -30, -30, 5, 10, 15, 15, 10,
-    5, -30, -40, -20, 0, 5, 5, 0, -20, -40, -50, -40, -30, -30, -30, -30, -40, -50,
-];
-
-const KNIGHT_SQUARE_TABLE_BLACK: [i32; 64] = [
-    -50, -40, -30, -30, -30, -30, -40, -50, -40, -20, 0, 5, 5, 0, -20, -40, -30, 5, 10, 15, 15, 10,
-    5, -30, -30, 0, 15, 20, 20, 15, 0, -30, -30, 5, 15, 20, 20, 15, 5, -30, -30, 0, 10, 15, 15, 10,
-    0, -30, -40, -20, 0, 0, 0, 0, -20, -40, -50, -40, -30, -30, -30, -30, -40, -50,
-];
-
-const BISHOP_SQUARE_TABLE_WHITE: [i32; 64] = [
-    -20, -10, -10, -10, -10, -10, -10, -20, -10, 0, 0, 0, 0, 0, 0, -10, -10, 0, 5, 10, 10, 5, 0,
-    -10, -10, 5, 5, 10, 10, 5, 5, -10, -10, 0, 10, 10, 10, 10, 0, -10, -10, 10, 10, 10, 10, 10, 10,
-    -10, -10, 5, 0, 0, 0, 0, 5, -10, -20, -10, -10, -10, -10, -10, -10, -20,
-];
-
-const BISHOP_SQUARE_TABLE_BLACK: [i32; 64] = [
-    -20, -10, -10, -10, -10, -10, -10, -20, -10, 5, 0, 0, 0, 0, 5, -10, -10, 10, 10, 10, 10, 10,
-    10, -10, -10, 0, 10, 10, 10, 10, 0, -10, -10, 5, 5, 10, 10, 5, 5, -10, -10, 0, 5, 10, 10, 5, 0,
-    -10, -10, 0, 0, 0, 0, 0, 0, -10, -20, -10, -10, -10, -10, -10, -10, -20,
-];
-
-const ROOK_SQUARE_TABLE_WHITE: [i32; 64] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 5, 10, 10, 10, 10, 10, 10, 5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0,
-    0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, 0, 0,
-    0, 5, 5, 0, 0, 0,
-];
-
-const ROOK_SQUARE_TABLE_BLACK: [i32; 64] = [
-    0, 0, 0, 5, 5, 0, 0, 0, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0,
-    0, -5, -5, 0, 0, 0, 0, 0, 0, -5, -5, 0, 0, 0, 0, 0, 0, -5, 5, 10, 10, 10, 10, 10, 10, 5, 0, 0,
-    0, 0, 0, 0, 0, 0,
-];
-
-const QUEEN_SQUARE_TABLE_WHITE: [i32; 64] = [
-    -20, -10, -10, -5, -5, -10, -10, -20, -10, 0, 0, 0, 0, 0, 0, -10, -10, 0, 5, 5, 5, 5, 0, -10,
-    -5, 0, 5, 5, 5, 5, 0, -5, 0, 0, 5, 5, 5, 5, 0, -5, -10, 5, 5, 5, 5, 5, 0, -10, -10, 0, 5, 0, 0,
-    0, 0, -10, -20, -10, -10, -5, -5, -10, -10, -20,
-];
-
-const QUEEN_SQUARE_TABLE_BLACK: [i32; 64] = [
-    -20, -10, -10, -5, -5, -10, -10, -20, -10, 0, 0, 0, 0, 5, 0, -10, -10, 0, 5, 5, 5, 5, 5, -10,
-    -5, 0, 5, 5, 5, 5, 0, 0, -5, 0, 5, 5, 5, 5, 0, -5, -10, 0, 5, 5, 5, 5, 0, -10, -10, 0, 0, 0, 0,
-    0, 0, -10, -20, -10, -10, -5, -5, -10, -10, -20,
-];
-
-const KING_SQUARE_TABLE_WHITE: [i32; 64] = [
-    -30, -40, -40, -50, -50, -40, -40, -30, -30, -40, -40, -50, -50, -40, -40, -30, -30, -40, -40,
-    -50, -50, -40, -40, -30, -30, -40, -40, -50, -50, -40, -40, -30, -20, -30, -30, -40, -40, -30,
-    -30, -20, -10, -20, -20, -20, -20, -20, -20, -10, 20, 20, 0, 0, 0, 0, 20, 20, 20, 30, 10, 0, 0,
-    10, 30, 20,
-];
-
-const KING_SQUARE_TABLE_BLACK: [i32; 64] = [
-    20, 30, 10, 0, 0, 10, 30, 20, 20, 20, 0, 0, 0, 0, 20, 20, -10, -20, -20, -20, -20, -20, -20,
-    -10, -20, -30, -30, -40, -40, -30, -30, -20, -30, -40, -40, -50, -50, -40, -40, -30, -30, -40,
-    -40, -50, -50, -40, -40, -30, -30, -40, -40, -50, -50, -40, -40, -30, -30, -40, -40, -50, -50,
-    -40, -40, -30,
-];
-
+// The order of the bitboards in `Chess`, and of every array with a value per piece type.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PieceType {
     King,
@@ -94,6 +19,8 @@ pub enum PieceType {
 }
 pub use PieceType::*;
 
+pub const PIECE_TYPES: [PieceType; 6] = [King, Queen, Rook, Bishop, Knight, Pawn];
+
 impl PieceType {
     pub fn evaluate_material(&self) -> i32 {
         match self {
@@ -121,44 +48,67 @@ impl Piece {
 
     pub fn gen_moves(&self, chess: &Chess) -> Vec<Move> {
         let mut moves = Vec::new();
+        self.push_moves(chess, &mut moves);
+        moves
+    }
 
+    pub fn attacks(&self, occupied: Bitboard) -> Bitboard {
+        let sq = usize::from(self.pos);
         match self.r#type {
-            King => {
-                let king_poses = [
-                    self.pos.up(1),
-                    self.pos.down(1),
-                    self.pos.left(1),
-                    self.pos.right(1),
-                    self.pos.up(1).right(1),
-                    self.pos.down(1).right(1),
-                    self.pos.down(1).left(1),
-                    self.pos.up(1).left(1),
-                ];
+            King => king_attacks(sq),
+            Queen => queen_attacks(sq, occupied),
+            Rook => rook_attacks(sq, occupied),
+            Bishop => bishop_attacks(sq, occupied),
+            Knight => knight_attacks(sq),
+            Pawn => pawn_attacks(self.color, sq),
+        }
+    }
+
+    pub(crate) fn push_moves(&self, chess: &Chess, moves: &mut Vec<Move>) {
+        let occupied = chess.occupied();
+        let enemies = chess.colors[!self.color as usize];
+
+        let targets = match self.r#type {
+            Pawn => {
+                let pawn_up_one = self.pos.up_color(self.color, 1);
+                let pawn_up_two = self.pos.up_color(self.color, 2);
+
+                if pawn_up_one.is_on_board() && !pawn_up_one.is_there_a_piece(chess) {
+                    self.push_pawn_move(pawn_up_one, moves);
 
-                for &king_pos in king_poses.iter() {
-                    if king_pos.is_on_board()
-                        && !king_pos.is_there_a_piece_color(&chess.board, self.color)
+                    if self.pos.is_pawn_starting_pos(self.color)
+                        && !pawn_up_two.is_there_a_piece(chess)
                     {
                         moves.push(Move {
                             from: self.pos,
-                            to: king_pos,
+                            to: pawn_up_two,
                             prom: None,
-                        })
+                        });
                     }
                 }
 
-                let castle_right = if chess.turn == White {
+                let en_passant = chess
+                    .en_passant
+                    .map_or(EMPTY, |en_passant| square_bb(usize::from(en_passant)));
+
+                for sq in squares(self.attacks(occupied) & (enemies | en_passant)) {
+                    self.push_pawn_move(sq.into(), moves)
+                }
+
+                return;
+            }
+            King => {
+                let castle_right = if self.color == White {
                     chess.white_castle
                 } else {
                     chess.black_castle
                 };
 
-                if castle_right.can_castle() {
+                if castle_right.can_castle() && !self.am_i_being_attacked(chess) {
                     if castle_right.can_kingside_castle()
-                        && !self.pos.right(1).is_there_a_piece(&chess.board)
-                        && !self.pos.right(2).is_there_a_piece(&chess.board)
-                        && !self.am_i_being_attacked(&chess.board)
-                        && !self.piece_right(1).am_i_being_attacked(&chess.board)
+                        && !self.pos.right(1).is_there_a_piece(chess)
+                        && !self.pos.right(2).is_there_a_piece(chess)
+                        && !self.piece_right(1).am_i_being_attacked(chess)
                     {
                         moves.push(Move {
                             from: self.pos,
@@ -167,11 +117,10 @@ impl Piece {
                         })
                     }
                     if castle_right.can_queenside_castle()
-                        && !self.pos.left(1).is_there_a_piece(&chess.board)
-                        && !self.pos.left(2).is_there_a_piece(&chess.board)
-                        && !self.pos.left(3).is_there_a_piece(&chess.board)
-                        && !self.am_i_being_attacked(&chess.board)
-                        && !self.piece_left(1).am_i_being_attacked(&chess.board)
+                        && !self.pos.left(1).is_there_a_piece(chess)
+                        && !self.pos.left(2).is_there_a_piece(chess)
+                        && !self.pos.left(3).is_there_a_piece(chess)
+                        && !self.piece_left(1).am_i_being_attacked(chess)
                     {
                         moves.push(Move {
                             from: self.pos,
@@ -180,318 +129,41 @@ impl Piece {
                         })
                     }
                 }
-            }
-            Queen => {
-                let queen_moves = [
-                    |pos: Position| pos.up(1),
-                    |pos: Position| pos.down(1),
-                    |pos: Position| pos.left(1),
-                    |pos: Position| pos.right(1),
-                    |pos: Position| pos.up(1).right(1),
-                    |pos: Position| pos.down(1).right(1),
-                    |pos: Position| pos.down(1).left(1),
-                    |pos: Position| pos.up(1).left(1),
-                ];
-
-                for &queen_move in queen_moves.iter() {
-                    let mut new_pos = queen_move(self.pos);
-
-                    while new_pos.is_on_board()
-                        && !new_pos.is_there_a_piece_color(&chess.board, self.color)
-                    {
-                        moves.push(Move {
-                            from: self.pos,
-                            to: new_pos,
-                            prom: None,
-                        });
-
-                        if new_pos.is_there_a_piece_color(&chess.board, !self.color) {
-                            break;
-                        }
-
-                        new_pos = queen_move(new_pos);
-                    }
-                }
-            }
-            Rook => {
-                let rook_moves = [
-                    |pos: Position| pos.up(1),
-                    |pos: Position| pos.down(1),
-                    |pos: Position| pos.left(1),
-                    |pos: Position| pos.right(1),
-                ];
-
-                for &rook_move in rook_moves.iter() {
-                    let mut new_pos = rook_move(self.pos);
-
-                    while new_pos.is_on_board()
-                        && !new_pos.is_there_a_piece_color(&chess.board, self.color)
-                    {
-                        moves.push(Move {
-                            from: self.pos,
-                            to: new_pos,
-                            prom: None,
-                        });
-
-                        if new_pos.is_there_a_piece_color(&chess.board, !self.color) {
-                            break;
-                        }
 
-                        new_pos = rook_move(new_pos);
-                    }
-                }
+                self.attacks(occupied)
             }
-            Bishop => {
-                let bishop_moves = [
-                    |pos: Position| pos.up(1).right(1),
-                    |pos: Position| pos.down(1).right(1),
-                    |pos: Position| pos.down(1).left(1),
-                    |pos: Position| pos.up(1).left(1),
-                ];
-
-                for &bishop_move in bishop_moves.iter() {
-                    let mut new_pos = bishop_move(self.pos);
-
-                    while new_pos.is_on_board()
-                        && !new_pos.is_there_a_piece_color(&chess.board, self.color)
-                    {
-                        moves.push(Move {
-                            from: self.pos,
-                            to: new_pos,
-                            prom: None,
-                        });
-
-                        if new_pos.is_there_a_piece_color(&chess.board, !self.color) {
-                            break;
-                        }
-
-                        new_pos = bishop_move(new_pos);
-                    }
-                }
-            }
-            Knight => {
-                let knight_poses = [
-                    self.pos.up(2).left(1),
-                    self.pos.up(2).right(1),
-                    self.pos.right(2).up(1),
-                    self.pos.right(2).down(1),
-                    self.pos.down(2).left(1),
-                    self.pos.down(2).right(1),
-                    self.pos.left(2).up(1),
-                    self.pos.left(2).down(1),
-                ];
-
-                for &knight_pos in knight_poses.iter() {
-                    if knight_pos.is_on_board()
-                        && !knight_pos.is_there_a_piece_color(&chess.board, self.color)
-                    {
-                        moves.push(Move {
-                            from: self.pos,
-                            to: knight_pos,
-                            prom: None,
-                        })
-                    }
-                }
-            }
-            Pawn => {
-                let pawn_up_one = self.pos.up_color(self.color, 1);
-                let pawn_up_two = self.pos.up_color(self.color, 2);
-                let pawn_right = pawn_up_one.right(1);
-                let pawn_left = pawn_up_one.left(1);
-
-                if pawn_up_one.is_on_board() && !pawn_up_one.is_there_a_piece(&chess.board) {
-                    if pawn_up_one.is_pawn_promotion_pos(self.color) {
-                        moves.extend_from_slice(&[
-                            Move {
-                                from: self.pos,
-                                to: pawn_up_one,
-                                prom: Some(Queen),
-                            },
-                            Move {
-                                from: self.pos,
-                                to: pawn_up_one,
-                                prom: Some(Rook),
-                            },
-                            Move {
-                                from: self.pos,
-                                to: pawn_up_one,
-                                prom: Some(Bishop),
-                            },
-                            Move {
-                                from: self.pos,
-                                to: pawn_up_one,
-                                prom: Some(Knight),
-                            },
-                        ])
-                    } else {
-                        moves.push(Move {
-                            from: self.pos,
-                            to: pawn_up_one,
-                            prom: None,
-                        });
-
-                        if pawn_up_two.is_on_board()
-                            && !pawn_up_two.is_there_a_piece(&chess.board)
-                            && self.pos.is_pawn_starting_pos(self.color)
-                        {
-                            moves.push(Move {
-                                from: self.pos,
-                                to: pawn_up_two,
-                                prom: None,
-                            });
-                        }
-                    }
-                }
-
-                if pawn_right.is_on_board()
-                    && (pawn_right.is_there_a_piece_color(&chess.board, !self.color)
-                        || pawn_right.can_en_passant(chess.en_passant))
-                {
-                    moves.push(Move {
-                        from: self.pos,
-                        to: pawn_right,
-                        prom: None,
-                    });
-                }
+            _ => self.attacks(occupied),
+        };
 
-                if pawn_left.is_on_board()
-                    && (pawn_left.is_there_a_piece_color(&chess.board, !self.color)
-                        || pawn_left.can_en_passant(chess.en_passant))
-                {
-                    moves.push(Move {
-                        from: self.pos,
-                        to: pawn_left,
-                        prom: None,
-                    });
-                }
-            }
+        for sq in squares(targets & !chess.colors[self.color as usize]) {
+            moves.push(Move {
+                from: self.pos,
+                to: sq.into(),
+                prom: None,
+            })
         }
-
-        moves
     }
 
-    pub fn am_i_being_attacked(&self, board: &[Square; 64]) -> bool {
-        let pawn_left = self.pos.up_color(self.color, 1).left(1);
-        let pawn_right = self.pos.up_color(self.color, 1).right(1);
-
-        let knight_poses = [
-            self.pos.up(2).left(1),
-            self.pos.up(2).right(1),
-            self.pos.right(2).up(1),
-            self.pos.right(2).down(1),
-            self.pos.down(2).left(1),
-            self.pos.down(2).right(1),
-            self.pos.left(2).up(1),
-            self.pos.left(2).down(1),
-        ];
-
-        let king_poses = [
-            self.pos.up(1).right(1),
-            self.pos.down(1).right(1),
-            self.pos.down(1).left(1),
-            self.pos.up(1).left(1),
-            self.pos.up(1),
-            self.pos.down(1),
-            self.pos.left(1),
-            self.pos.right(1),
-        ];
-
-        let bishop_moves = [
-            |pos: Position| pos.up(1).right(1),
-            |pos: Position| pos.down(1).right(1),
-            |pos: Position| pos.down(1).left(1),
-            |pos: Position| pos.up(1).left(1),
-        ];
-
-        let rook_moves = [
-            |pos: Position| pos.up(1),
-            |pos: Position| pos.down(1),
-            |pos: Position| pos.left(1),
-            |pos: Position| pos.right(1),
-        ];
-
-        if pawn_left.is_on_board()
-            && pawn_left.is_there_a_piece_type_color(board, Pawn, !self.color)
-        {
-            return true;
-        }
-
-        if pawn_right.is_on_board()
-            && pawn_right.is_there_a_piece_type_color(board, Pawn, !self.color)
-        {
-            return true;
-        }
-
-        for &knight_pos in knight_poses.iter() {
-            if knight_pos.is_on_board()
-                && knight_pos.is_there_a_piece_type_color(board, Knight, !self.color)
-            {
-                return true;
-            }
-        }
-
-        for &king_pos in king_poses.iter() {
-            if king_pos.is_on_board()
-                && king_pos.is_there_a_piece_type_color(board, King, !self.color)
-            {
-                return true;
-            }
-        }
-
-        for &bishop_move in bishop_moves.iter() {
-            let mut new_pos = bishop_move(self.pos);
-
-            while new_pos.is_on_board() && !new_pos.is_there_a_piece_color(board, self.color) {
-                if new_pos.is_there_a_piece_type_color(board, Bishop, !self.color)
-                    || new_pos.is_there_a_piece_type_color(board, Queen, !self.color)
-                {
-                    return true;
-                } else if new_pos.is_there_a_piece_color(board, !self.color) {
-                    break;
-                } else {
-                    new_pos = bishop_move(new_pos);
-                }
+    fn push_pawn_move(&self, to: Position, moves: &mut Vec<Move>) {
+        if to.is_pawn_promotion_pos(self.color) {
+            for &prom in &[Queen, Rook, Bishop, Knight] {
+                moves.push(Move {
+                    from: self.pos,
+                    to,
+                    prom: Some(prom),
+                })
             }
+        } else {
+            moves.push(Move {
+                from: self.pos,
+                to,
+                prom: None,
+            })
         }
-
-        for &rook_move in rook_moves.iter() {
-            let mut new_pos = rook_move(self.pos);
-
-            while new_pos.is_on_board() && !new_pos.is_there_a_piece_color(board, self.color) {
-                if new_pos.is_there_a_piece_type_color(board, Rook, !self.color)
-                    || new_pos.is_there_a_piece_type_color(board, Queen, !self.color)
-                {
-                    return true;
-                } else if new_pos.is_there_a_piece_color(board, !self.color) {
-                    break;
-                } else {
-                    new_pos = rook_move(new_pos);
-                }
-            }
-        }
-
-        false
     }
 
-    pub fn evaluate(&self) -> i32 {
-        let material_value = self.r#type.evaluate_material();
-
-        let square_table = match (self.r#type, self.color) {
-            (King, White) => KING_SQUARE_TABLE_WHITE,
-            (King, Black) => KING_SQUARE_TABLE_BLACK,
-            (Queen, White) => QUEEN_SQUARE_TABLE_WHITE,
-            (Queen, Black) => QUEEN_SQUARE_TABLE_BLACK,
-            (Rook, White) => ROOK_SQUARE_TABLE_WHITE,
-            (Rook, Black) => ROOK_SQUARE_TABLE_BLACK,
-            (Bishop, White) => BISHOP_SQUARE_TABLE_WHITE,
-            (Bishop, Black) => BISHOP_SQUARE_TABLE_BLACK,
-            (Knight, White) => KNIGHT_SQUARE_TABLE_WHITE,
-            (Knight, Black) => KNIGHT_SQUARE_TABLE_BLACK,
-            (Pawn, White) => PAWN_SQUARE_TABLE_WHITE,
-            (Pawn, Black) => PAWN_SQUARE_TABLE_BLACK,
-        };
-
-        material_value + square_table[usize::from(self.pos)]
+    pub fn am_i_being_attacked(&self, chess: &Chess) -> bool {
+        chess.is_square_attacked(self.pos, !self.color)
     }
 
     fn piece_right(mut self, num: i32) -> Self {
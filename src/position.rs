@@ -1,7 +1,8 @@
 use std::convert::{TryFrom, TryInto};
 
 use crate::{
-    board::{Color, Square, White},
+    bitboard::{square_bb, EMPTY},
+    board::{Chess, Color, White},
     piece::PieceType,
 };
 
@@ -79,7 +80,7 @@ pub const H6: Position = Position::new(2, 7);
 pub const H7: Position = Position::new(1, 7);
 pub const H8: Position = Position::new(0, 7);
 
-#[derive(Clone, Copy, PartialEq, Default, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Default, Hash, Debug)]
 pub struct Position {
     pub row: i32,
     pub col: i32,
@@ -94,41 +95,25 @@ impl Position {
         -1 < self.row && self.row < 8 && -1 < self.col && self.col < 8
     }
 
-    pub fn is_there_a_piece(self, board: &[Square; 64]) -> bool {
-        board[usize::from(self)].is_some()
+    pub fn is_there_a_piece(self, chess: &Chess) -> bool {
+        chess.occupied() & square_bb(usize::from(self)) != EMPTY
     }
 
-    pub fn is_there_a_piece_color(self, board: &[Square; 64], color: Color) -> bool {
-        if let Some(piece) = board[usize::from(self)] {
-            piece.color == color
-        } else {
-            false
-        }
+    pub fn is_there_a_piece_color(self, chess: &Chess, color: Color) -> bool {
+        chess.colors[color as usize] & square_bb(usize::from(self)) != EMPTY
     }
 
     pub fn is_there_a_piece_type_color(
         self,
-        board: &[Square; 64],
+        chess: &Chess,
         piece_type: PieceType,
         color: Color,
     ) -> bool {
-        if let Some(piece) = board[usize::from(self)] {
-            piece.r#type == piece_type && piece.color == color
-        } else {
-            false
-        }
+        chess.pieces_color(piece_type, color) & square_bb(usize::from(self)) != EMPTY
     }
 
     pub fn can_en_passant(self, en_passant: Option<Position>) -> bool {
-        if let Some(en_passant) = en_passant {
-            if self == en_passant {
-                true
-            } else {
-                false
-            }
-        } else {
-            false
-        }
+        en_passant == Some(self)
     }
 
     pub fn is_pawn_starting_pos(self, color: Color) -> bool {
@@ -213,9 +198,7 @@ impl TryFrom<&str> for Position {
     type Error = Error;
 
     fn try_from(pos: &str) -> Result<Self, Self::Error> {
-        let mut res = Position::default();
-
-        res.col = match pos.as_bytes().get(0) {
+        let col = match pos.as_bytes().first() {
             Some(&b'a') => 0,
             Some(&b'b') => 1,
             Some(&b'c') => 2,
@@ -227,7 +210,7 @@ impl TryFrom<&str> for Position {
             _ => bail!("error parsing position: {}", pos),
         };
 
-        res.row = match pos.as_bytes().get(1) {
+        let row = match pos.as_bytes().get(1) {
             Some(&b'8') => 0,
             Some(&b'7') => 1,
             Some(&b'6') => 2,
@@ -239,15 +222,15 @@ impl TryFrom<&str> for Position {
             _ => bail!("error parsing position: {}", pos),
         };
 
-        Ok(res)
+        Ok(Position { row, col })
     }
 }
 
-impl Into<String> for Position {
-    fn into(self) -> String {
+impl From<Position> for String {
+    fn from(pos: Position) -> Self {
         format!(
             "{}{}",
-            match self.col {
+            match pos.col {
                 0 => "a",
                 1 => "b",
                 2 => "c",
@@ -258,7 +241,7 @@ impl Into<String> for Position {
                 7 => "h",
                 _ => panic!("position col error"),
             },
-            match self.row {
+            match pos.row {
                 7 => "1",
                 6 => "2",
                 5 => "3",
@@ -273,7 +256,7 @@ impl Into<String> for Position {
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub struct Move {
     pub from: Position,
     pub to: Position,
@@ -284,20 +267,7 @@ impl TryFrom<String> for Move {
     type Error = Error;
 
     fn try_from(r#move: String) -> Result<Self, Self::Error> {
-        match r#move.as_str() {
-            _ => Ok(Move {
-                from: (&r#move[0..2]).try_into()?,
-                to: (&r#move[2..4]).try_into()?,
-                prom: match r#move.as_bytes().get(4) {
-                    Some(&b'k') => Some(PieceType::Knight),
-                    Some(&b'b') => Some(PieceType::Bishop),
-                    Some(&b'r') => Some(PieceType::Rook),
-                    Some(&b'q') => Some(PieceType::Queen),
-                    Some(_) => bail!("error parsing move: {}", r#move),
-                    None => None,
-                },
-            }),
-        }
+        r#move.as_str().try_into()
     }
 }
 
@@ -305,31 +275,33 @@ impl TryFrom<&str> for Move {
     type Error = Error;
 
     fn try_from(r#move: &str) -> Result<Self, Self::Error> {
-        match r#move {
-            _ => Ok(Move {
-                from: (&r#move[0..2]).try_into()?,
-                to: (&r#move[2..4]).try_into()?,
-                prom: match r#move.as_bytes().get(4) {
-                    Some(&b'k') => Some(PieceType::Knight),
-                    Some(&b'b') => Some(PieceType::Bishop),
-                    Some(&b'r') => Some(PieceType::Rook),
-                    Some(&b'q') => Some(PieceType::Queen),
-                    Some(_) => bail!("error parsing move: {}", r#move),
-                    None => None,
-                },
-            }),
+        if !(4..=5).contains(&r#move.len()) || !r#move.is_ascii() {
+            bail!("error parsing move: {}", r#move)
         }
+
+        Ok(Move {
+            from: (&r#move[0..2]).try_into()?,
+            to: (&r#move[2..4]).try_into()?,
+            prom: match r#move.as_bytes().get(4) {
+                Some(&b'n') => Some(PieceType::Knight),
+                Some(&b'b') => Some(PieceType::Bishop),
+                Some(&b'r') => Some(PieceType::Rook),
+                Some(&b'q') => Some(PieceType::Queen),
+                Some(_) => bail!("error parsing move: {}", r#move),
+                None => None,
+            },
+        })
     }
 }
 
-impl Into<String> for Move {
-    fn into(self) -> String {
+impl From<Move> for String {
+    fn from(r#move: Move) -> Self {
         format!(
             "{}{}{}",
-            Into::<String>::into(self.from),
-            Into::<String>::into(self.to),
-            match self.prom {
-                Some(PieceType::Knight) => "k",
+            String::from(r#move.from),
+            String::from(r#move.to),
+            match r#move.prom {
+                Some(PieceType::Knight) => "n",
                 Some(PieceType::Bishop) => "b",
                 Some(PieceType::Rook) => "r",
                 Some(PieceType::Queen) => "q",
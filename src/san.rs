@@ -0,0 +1,183 @@
+use std::convert::TryInto;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    position::{Move, Position},
+    Chess,
+    PieceType::{self, *},
+};
+
+impl Chess {
+    pub fn move_to_san(&self, r#move: Move) -> Result<String> {
+        let legal_moves = self.gen_legal_moves();
+        if !legal_moves.contains(&r#move) {
+            bail!("illegal move: {}", String::from(r#move))
+        }
+
+        let piece_type = match self.piece_at(r#move.from) {
+            Some(piece) => piece.r#type,
+            None => bail!("no piece on {}", String::from(r#move.from)),
+        };
+
+        let mut san = String::new();
+        let Move { from, to, prom } = r#move;
+
+        if piece_type == King && (to.col - from.col).abs() == 2 {
+            san.push_str(if to.col > from.col { "O-O" } else { "O-O-O" })
+        } else {
+            let is_capture = self.is_capture(r#move);
+
+            if piece_type == Pawn {
+                if is_capture {
+                    san.push(file_char(from))
+                }
+            } else {
+                san.push(piece_letter(piece_type));
+
+                let others: Vec<Position> = legal_moves
+                    .iter()
+                    .filter(|other| other.to == to && other.from != from)
+                    .filter(|other| self.piece_type_at(other.from) == Some(piece_type))
+                    .map(|other| other.from)
+                    .collect();
+
+                if !others.is_empty() {
+                    if others.iter().all(|other| other.col != from.col) {
+                        san.push(file_char(from))
+                    } else if others.iter().all(|other| other.row != from.row) {
+                        san.push(rank_char(from))
+                    } else {
+                        san.push(file_char(from));
+                        san.push(rank_char(from))
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x')
+            }
+            san.push_str(&String::from(to));
+
+            if let Some(prom) = prom {
+                san.push('=');
+                san.push(piece_letter(prom))
+            }
+        }
+
+        let mut chess = self.clone();
+        chess.set(r#move);
+        if chess.is_check() {
+            san.push(if chess.gen_legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            })
+        }
+
+        Ok(san)
+    }
+
+    pub fn parse_san(&self, san: &str) -> Result<Move> {
+        let trimmed = san.trim().trim_end_matches(|chr| "+#!?".contains(chr));
+
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(2),
+            "O-O-O" | "0-0-0" => Some(-2),
+            _ => None,
+        };
+        if let Some(offset) = castle {
+            return self
+                .gen_legal_moves()
+                .into_iter()
+                .find(|r#move| {
+                    self.piece_type_at(r#move.from) == Some(King)
+                        && r#move.to.col - r#move.from.col == offset
+                })
+                .ok_or_else(|| anyhow!("illegal castling: {}", san));
+        }
+
+        let mut chars: Vec<char> = trimmed.chars().collect();
+
+        // the promotion piece may be written as "e8=Q", "e8Q" or "e8q"
+        let mut prom = None;
+        if chars.len() > 2 && !chars[chars.len() - 1].is_ascii_digit() {
+            prom = match chars.pop().unwrap().to_ascii_uppercase() {
+                'Q' => Some(Queen),
+                'R' => Some(Rook),
+                'B' => Some(Bishop),
+                'N' => Some(Knight),
+                _ => bail!("error parsing SAN move: {}", san),
+            };
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        let piece_type = match chars.first() {
+            Some('K') => King,
+            Some('Q') => Queen,
+            Some('R') => Rook,
+            Some('B') => Bishop,
+            Some('N') => Knight,
+            _ => Pawn,
+        };
+        if piece_type != Pawn {
+            chars.remove(0);
+        }
+
+        if chars.len() < 2 {
+            bail!("error parsing SAN move: {}", san)
+        }
+        let to_str: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to: Position = to_str.as_str().try_into()?;
+
+        let mut from_col = None;
+        let mut from_row = None;
+        for chr in chars {
+            match chr {
+                'a'..='h' => from_col = Some(chr as i32 - 'a' as i32),
+                '1'..='8' => from_row = Some('8' as i32 - chr as i32),
+                'x' | ':' | '-' => (),
+                _ => bail!("error parsing SAN move: {}", san),
+            }
+        }
+
+        let mut candidates = self.gen_legal_moves().into_iter().filter(|r#move| {
+            r#move.to == to
+                && r#move.prom == prom
+                && self.piece_type_at(r#move.from) == Some(piece_type)
+                && from_col.is_none_or(|col| r#move.from.col == col)
+                && from_row.is_none_or(|row| r#move.from.row == row)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(r#move), None) => Ok(r#move),
+            (Some(_), Some(_)) => bail!("ambiguous SAN move: {}", san),
+            (None, _) => bail!("illegal SAN move: {}", san),
+        }
+    }
+
+    fn piece_type_at(&self, pos: Position) -> Option<PieceType> {
+        self.piece_at(pos).map(|piece| piece.r#type)
+    }
+}
+
+fn piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        King => 'K',
+        Queen => 'Q',
+        Rook => 'R',
+        Bishop => 'B',
+        Knight => 'N',
+        Pawn => 'P',
+    }
+}
+
+fn file_char(pos: Position) -> char {
+    (b'a' + pos.col as u8) as char
+}
+
+fn rank_char(pos: Position) -> char {
+    (b'8' - pos.row as u8) as char
+}
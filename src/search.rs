@@ -0,0 +1,652 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    bitboard::EMPTY,
+    board::Color::{self, *},
+    eval,
+    params::EvalParams,
+    pawns::PawnTable,
+    piece::PieceType::{King, Pawn},
+    position::Move,
+    tt::{score_from_tt, Bound, TranspositionTable, DEFAULT_HASH_MB},
+    Chess,
+};
+
+pub const MATE: i32 = 1_000_000;
+pub const INFINITY: i32 = MATE + 1;
+pub const MAX_PLY: i32 = 128;
+
+// A capture that cannot bring the score back above alpha even with this much
+// positional compensation is not worth searching.
+const DELTA_MARGIN: i32 = 200;
+
+// Moves searched at full depth before the late move reductions kick in, and the
+// depth below which nothing is reduced or pruned with a null move.
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+const REDUCTION_MIN_DEPTH: i32 = 3;
+
+// Time kept in reserve for the GUI and the communication lag, in milliseconds.
+const MOVE_OVERHEAD: u64 = 30;
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+
+#[derive(Clone, Copy, Default, Debug)]
+pub struct SearchLimits {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u64>,
+    pub movetime: Option<u64>,
+    pub depth: Option<i32>,
+    pub nodes: Option<u64>,
+    pub infinite: bool,
+}
+
+impl SearchLimits {
+    // Returns the soft limit, after which no new iteration is started, and the hard
+    // limit, after which the current iteration is aborted.
+    pub fn time_budget(&self, turn: Color) -> (Option<Duration>, Option<Duration>) {
+        if self.infinite {
+            return (None, None);
+        }
+
+        if let Some(movetime) = self.movetime {
+            let movetime = Duration::from_millis(movetime.saturating_sub(MOVE_OVERHEAD));
+            return (Some(movetime), Some(movetime));
+        }
+
+        let (time, inc) = match turn {
+            White => (self.wtime, self.winc),
+            Black => (self.btime, self.binc),
+        };
+
+        match time {
+            Some(time) => {
+                let time = time.saturating_sub(MOVE_OVERHEAD);
+                let inc = inc.unwrap_or(0);
+                let moves_to_go = self.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+                let soft = (time / moves_to_go + inc * 3 / 4).min(time);
+                let hard = (soft * 4).min(time);
+
+                (
+                    Some(Duration::from_millis(soft)),
+                    Some(Duration::from_millis(hard)),
+                )
+            }
+            None => (None, None),
+        }
+    }
+}
+
+// The parts of the search that can be turned off, to measure what they are worth.
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions {
+    pub null_move: bool,
+    pub late_move_reductions: bool,
+    pub pvs: bool,
+    pub check_extensions: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            null_move: true,
+            late_move_reductions: true,
+            pvs: true,
+            check_extensions: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: i32,
+    pub pv: Vec<Move>,
+    pub depth: i32,
+    pub seldepth: i32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+}
+
+impl SearchResult {
+    // Moves until mate, negative when the side to move is the one being mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        if self.score > MATE - MAX_PLY {
+            Some((MATE - self.score + 1) / 2)
+        } else if self.score < -MATE + MAX_PLY {
+            Some(-(MATE + self.score) / 2)
+        } else {
+            None
+        }
+    }
+
+    pub fn nps(&self) -> u64 {
+        match self.elapsed.as_millis() as u64 {
+            0 => 0,
+            millis => self.nodes * 1000 / millis,
+        }
+    }
+}
+
+pub struct Searcher {
+    pub tt: TranspositionTable,
+    pub pawn_table: PawnTable,
+    pub options: SearchOptions,
+    params: EvalParams,
+
+    // Shared with the threads that control the search: raising `stop` aborts it, and
+    // time limits are ignored while `pondering` is set.
+    stop: Arc<AtomicBool>,
+    pondering: Arc<AtomicBool>,
+
+    nodes: u64,
+    seldepth: i32,
+    pv: Vec<Vec<Move>>,
+    start: Instant,
+    // when the time limits started to run: the start of the search, or the moment
+    // pondering ended
+    clock_start: Option<Instant>,
+    hard_limit: Option<Duration>,
+    node_limit: Option<u64>,
+    completed_depth: i32,
+    stopped: bool,
+}
+
+impl Default for Searcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl Searcher {
+    pub fn new(hash_mb: usize) -> Self {
+        Self {
+            tt: TranspositionTable::new(hash_mb),
+            pawn_table: PawnTable::new(),
+            options: SearchOptions::default(),
+            params: EvalParams::default(),
+            stop: Arc::new(AtomicBool::new(false)),
+            pondering: Arc::new(AtomicBool::new(false)),
+            nodes: 0,
+            seldepth: 0,
+            pv: vec![Vec::new(); MAX_PLY as usize + 1],
+            start: Instant::now(),
+            clock_start: None,
+            hard_limit: None,
+            node_limit: None,
+            completed_depth: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    // The cached pawn entries were computed with the old parameters.
+    pub fn set_params(&mut self, params: EvalParams) {
+        self.params = params;
+        self.pawn_table.clear()
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    pub fn ponder_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.pondering)
+    }
+
+    pub fn search(&mut self, chess: &Chess, limits: &SearchLimits) -> SearchResult {
+        self.search_with_info(chess, limits, |_| ())
+    }
+
+    // Like `search`, but reports the partial result after every completed iteration
+    // and every new best move found at the root.
+    pub fn search_with_info(
+        &mut self,
+        chess: &Chess,
+        limits: &SearchLimits,
+        mut on_info: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let (soft_limit, hard_limit) = limits.time_budget(chess.turn);
+
+        self.nodes = 0;
+        self.start = Instant::now();
+        self.clock_start = if self.pondering.load(Ordering::Relaxed) {
+            None
+        } else {
+            Some(self.start)
+        };
+        self.hard_limit = hard_limit;
+        self.node_limit = limits.nodes;
+        self.completed_depth = 0;
+        self.stopped = false;
+        self.tt.new_search();
+
+        let mut chess = chess.clone();
+        let legal_moves = chess.gen_legal_moves();
+
+        let mut result = SearchResult {
+            best_move: legal_moves.first().copied(),
+            score: match (legal_moves.is_empty(), chess.is_check()) {
+                (true, true) => -MATE,
+                _ => 0,
+            },
+            pv: Vec::new(),
+            depth: 0,
+            seldepth: 0,
+            nodes: 0,
+            elapsed: Duration::default(),
+        };
+
+        let max_depth = limits.depth.unwrap_or(MAX_PLY - 1).clamp(1, MAX_PLY - 1);
+
+        for depth in 1..=max_depth {
+            self.seldepth = 0;
+
+            let score = self.search_root(&mut chess, legal_moves.clone(), depth, &mut on_info);
+            if self.stopped {
+                break;
+            }
+
+            if let Some(score) = score {
+                result = self.result(depth, score);
+                on_info(&result);
+            }
+            self.completed_depth = depth;
+
+            if soft_limit
+                .zip(self.clock_elapsed())
+                .is_some_and(|(soft_limit, elapsed)| elapsed >= soft_limit)
+            {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result.elapsed = self.start.elapsed();
+        result
+    }
+
+    fn result(&self, depth: i32, score: i32) -> SearchResult {
+        SearchResult {
+            best_move: self.pv[0].first().copied(),
+            score,
+            pv: self.pv[0].clone(),
+            depth,
+            seldepth: self.seldepth,
+            nodes: self.nodes,
+            elapsed: self.start.elapsed(),
+        }
+    }
+
+    fn update_pv(&mut self, ply: i32, r#move: Move) {
+        let (pv, child_pv) = self.pv.split_at_mut(ply as usize + 1);
+        let pv = &mut pv[ply as usize];
+
+        pv.clear();
+        pv.push(r#move);
+        pv.extend_from_slice(&child_pv[0]);
+    }
+
+    fn should_stop(&mut self) -> bool {
+        // the first iteration always completes, so that there is a move to play
+        if self.completed_depth == 0 {
+            return false;
+        }
+
+        if self.nodes & 1023 == 0 {
+            let out_of_time = self
+                .hard_limit
+                .zip(self.clock_elapsed())
+                .is_some_and(|(hard_limit, elapsed)| elapsed >= hard_limit);
+            let out_of_nodes = self
+                .node_limit
+                .is_some_and(|node_limit| self.nodes >= node_limit);
+
+            self.stopped = self.stop.load(Ordering::Relaxed) || out_of_time || out_of_nodes;
+        }
+
+        self.stopped
+    }
+
+    // The time the limits are measured against, none while pondering.
+    fn clock_elapsed(&mut self) -> Option<Duration> {
+        if self.pondering.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(self.clock_start.get_or_insert_with(Instant::now).elapsed())
+    }
+
+    fn search_root(
+        &mut self,
+        chess: &mut Chess,
+        legal_moves: Vec<Move>,
+        depth: i32,
+        on_info: &mut impl FnMut(&SearchResult),
+    ) -> Option<i32> {
+        let mut r#move = None;
+        let mut alpha = -INFINITY;
+        let beta = INFINITY;
+
+        let tt_move = self.tt.probe(chess.hash).and_then(|entry| entry.best_move);
+        let ordered_moves = order_moves(chess, legal_moves, tt_move);
+
+        for legal_move in ordered_moves {
+            let undo = chess.make_move(legal_move);
+            let mut eval = -INFINITY;
+            if r#move.is_some() && self.options.pvs {
+                eval = -self.negamax(chess, -alpha - 1, -alpha, depth - 1, 1, true);
+            }
+            if eval > alpha || r#move.is_none() || !self.options.pvs {
+                eval = -self.negamax(chess, -beta, -alpha, depth - 1, 1, true);
+            }
+            chess.unmake_move(legal_move, undo);
+
+            if self.stopped {
+                return None;
+            }
+
+            if eval > alpha {
+                let is_new_best = r#move.is_some();
+
+                alpha = eval;
+                r#move = Some(legal_move);
+                self.update_pv(0, legal_move);
+
+                if is_new_best && depth > 1 {
+                    on_info(&self.result(depth, alpha));
+                }
+            }
+        }
+
+        if r#move.is_some() {
+            self.tt
+                .store(chess.hash, depth, Bound::Exact, alpha, r#move, 0);
+        }
+
+        r#move.map(|_| alpha)
+    }
+
+    fn negamax(
+        &mut self,
+        chess: &mut Chess,
+        mut alpha: i32,
+        beta: i32,
+        mut depth: i32,
+        ply: i32,
+        allow_null: bool,
+    ) -> i32 {
+        self.pv[ply as usize].clear();
+
+        if chess.is_repetition() || chess.is_fifty_move_draw() || chess.is_insufficient_material() {
+            return 0;
+        }
+
+        if ply >= MAX_PLY {
+            return self.evaluate(chess);
+        }
+
+        let in_check = chess.is_check();
+        if in_check && self.options.check_extensions {
+            depth += 1
+        }
+
+        if depth <= 0 {
+            return self.quiescence(chess, alpha, beta, ply);
+        }
+
+        self.seldepth = self.seldepth.max(ply);
+
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        let is_pv = beta - alpha > 1;
+
+        // cutting a PV node off would cut the principal variation short, so there the
+        // entry only orders the moves
+        let tt_entry = self.tt.probe(chess.hash);
+        if let Some(entry) = tt_entry {
+            if !is_pv && entry.depth >= depth {
+                let score = score_from_tt(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => (),
+                }
+            }
+        }
+
+        // Passing the turn is usually the worst move there is, so when it still fails
+        // high a real move would too. Not in pawn endgames, where zugzwang is common.
+        if self.options.null_move
+            && allow_null
+            && !is_pv
+            && !in_check
+            && depth >= REDUCTION_MIN_DEPTH
+            && has_pieces(chess)
+            && self.evaluate(chess) >= beta
+        {
+            let reduction = if depth > 6 { 3 } else { 2 };
+            let undo = chess.make_null_move();
+            let eval = -self.negamax(
+                chess,
+                -beta,
+                -beta + 1,
+                depth - 1 - reduction,
+                ply + 1,
+                false,
+            );
+            chess.unmake_null_move(undo);
+
+            if self.stopped {
+                return 0;
+            }
+            if eval >= beta {
+                // a mate found after passing is not a real one
+                return if eval >= MATE - MAX_PLY { beta } else { eval };
+            }
+        }
+
+        let legal_moves = chess.gen_legal_moves();
+        if legal_moves.is_empty() {
+            if !in_check {
+                return 0;
+            } else {
+                return -MATE + ply;
+            }
+        }
+
+        let ordered_moves = order_moves(
+            chess,
+            legal_moves,
+            tt_entry.and_then(|entry| entry.best_move),
+        );
+
+        let alpha_orig = alpha;
+        let mut best_eval = -INFINITY;
+        let mut best_move = None;
+
+        for (index, legal_move) in ordered_moves.into_iter().enumerate() {
+            let is_quiet = !chess.is_capture(legal_move) && legal_move.prom.is_none();
+            let undo = chess.make_move(legal_move);
+
+            // Only the first move gets a full window: the others are searched with a
+            // zero window to prove them worse, and late quiet moves at a lower depth,
+            // then searched again in full when that fails.
+            let eval = if index == 0 {
+                -self.negamax(chess, -beta, -alpha, depth - 1, ply + 1, true)
+            } else {
+                let reduction = if self.options.late_move_reductions
+                    && is_quiet
+                    && !in_check
+                    && !chess.is_check()
+                    && depth >= REDUCTION_MIN_DEPTH
+                    && index >= LMR_FULL_DEPTH_MOVES
+                {
+                    if index >= 2 * LMR_FULL_DEPTH_MOVES && depth >= 6 {
+                        2
+                    } else {
+                        1
+                    }
+                } else {
+                    0
+                };
+                let window = if self.options.pvs { -alpha - 1 } else { -beta };
+
+                let mut eval = alpha + 1;
+                if reduction > 0 {
+                    eval =
+                        -self.negamax(chess, window, -alpha, depth - 1 - reduction, ply + 1, true)
+                }
+                if eval > alpha && self.options.pvs {
+                    eval = -self.negamax(chess, window, -alpha, depth - 1, ply + 1, true)
+                }
+                if eval > alpha && (eval < beta || !self.options.pvs) {
+                    eval = -self.negamax(chess, -beta, -alpha, depth - 1, ply + 1, true)
+                }
+                eval
+            };
+            chess.unmake_move(legal_move, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if eval > best_eval {
+                best_eval = eval;
+                best_move = Some(legal_move);
+            }
+
+            if eval >= beta {
+                break;
+            }
+
+            if eval > alpha {
+                alpha = eval;
+                self.update_pv(ply, legal_move);
+            }
+        }
+
+        let bound = if best_eval >= beta {
+            Bound::Lower
+        } else if best_eval > alpha_orig {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.tt
+            .store(chess.hash, depth, bound, best_eval, best_move, ply);
+
+        best_eval
+    }
+
+    fn quiescence(&mut self, chess: &mut Chess, mut alpha: i32, beta: i32, ply: i32) -> i32 {
+        self.nodes += 1;
+        self.seldepth = self.seldepth.max(ply);
+        if self.should_stop() {
+            return 0;
+        }
+
+        if ply >= MAX_PLY {
+            return self.evaluate(chess);
+        }
+
+        let in_check = chess.is_check();
+
+        let (stand_pat, moves) = if in_check {
+            let evasions = chess.gen_legal_moves();
+            if evasions.is_empty() {
+                return -MATE + ply;
+            }
+            (-INFINITY, evasions)
+        } else {
+            let stand_pat = self.evaluate(chess);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            (stand_pat, chess.gen_legal_captures())
+        };
+
+        let mut best_eval = stand_pat;
+
+        for (_, r#move) in chess.sort_moves(moves) {
+            if !in_check && r#move.prom.is_none() {
+                let gain = chess
+                    .piece_at(r#move.to)
+                    .map_or(Pawn.evaluate_material(), |victim| {
+                        victim.r#type.evaluate_material()
+                    });
+                if stand_pat + gain + DELTA_MARGIN <= alpha {
+                    continue;
+                }
+            }
+
+            let undo = chess.make_move(r#move);
+            let eval = -self.quiescence(chess, -beta, -alpha, ply + 1);
+            chess.unmake_move(r#move, undo);
+
+            if self.stopped {
+                return 0;
+            }
+
+            if eval > best_eval {
+                best_eval = eval;
+            }
+
+            if eval >= beta {
+                break;
+            }
+
+            alpha = alpha.max(eval);
+        }
+
+        best_eval
+    }
+}
+
+impl Searcher {
+    fn evaluate(&mut self, chess: &Chess) -> i32 {
+        let score = eval::evaluate(chess, &self.params, Some(&mut self.pawn_table));
+        match chess.turn {
+            White => score,
+            Black => -score,
+        }
+    }
+}
+
+// Whether the side to move has anything besides the king and pawns.
+fn has_pieces(chess: &Chess) -> bool {
+    let pawns_and_king = chess.pieces[Pawn as usize] | chess.pieces[King as usize];
+    chess.colors[chess.turn as usize] & !pawns_and_king != EMPTY
+}
+
+fn order_moves(chess: &Chess, legal_moves: Vec<Move>, tt_move: Option<Move>) -> Vec<Move> {
+    let mut ordered_moves: Vec<Move> = chess
+        .sort_moves(legal_moves)
+        .into_iter()
+        .map(|(_, r#move)| r#move)
+        .collect();
+
+    if let Some(index) = ordered_moves
+        .iter()
+        .position(|&r#move| Some(r#move) == tt_move)
+    {
+        ordered_moves[..=index].rotate_right(1)
+    }
+
+    ordered_moves
+}
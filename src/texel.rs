@@ -0,0 +1,174 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{
+    board::Color::White,
+    params::EvalParams,
+    piece::PieceType::{King, Pawn},
+    Chess,
+};
+
+pub struct TuningEntry {
+    pub chess: Chess,
+    // 1 for a white win, 0.5 for a draw and 0 for a black win
+    pub result: f64,
+}
+
+impl TuningEntry {
+    // A quiet position and the result of the game it comes from, either after the FEN
+    // as 1-0, 0-1, 1/2-1/2 or in brackets as [1.0], [0.5], [0.0].
+    pub fn parse(line: &str) -> Option<Self> {
+        let result = if line.contains("1/2-1/2") || line.contains("[0.5]") {
+            0.5
+        } else if line.contains("1-0") || line.contains("[1.0]") {
+            1.0
+        } else if line.contains("0-1") || line.contains("[0.0]") {
+            0.0
+        } else {
+            return None;
+        };
+
+        // board, side to move, castling rights and en passant square, then the move
+        // counters if there are any
+        let mut tokens = line.split_ascii_whitespace();
+        let mut fen: Vec<&str> = tokens.by_ref().take(4).collect();
+        fen.extend(
+            tokens
+                .take(2)
+                .take_while(|token| token.parse::<u32>().is_ok()),
+        );
+
+        let chess = Chess::from_fen_lenient(&fen.join(" ")).ok()?;
+        Some(Self { chess, result })
+    }
+}
+
+// Every value of the parameters that can change an evaluation: the field, the score
+// in it and whether it is the endgame half.
+pub fn tunable_values(params: &EvalParams) -> Vec<(usize, usize, bool)> {
+    params
+        .fields()
+        .iter()
+        .enumerate()
+        .flat_map(|(field, &(name, scores))| {
+            (0..scores.len())
+                .filter(move |&index| is_used(name, index))
+                .flat_map(move |index| vec![(field, index, false), (field, index, true)])
+        })
+        .collect()
+}
+
+// Both sides always have a king, pawns never stand on the first or last rank and
+// kings and pawns have no mobility score.
+fn is_used(name: &str, index: usize) -> bool {
+    match name {
+        "material" => index != King as usize,
+        "psqt_pawn" => (8..56).contains(&index),
+        "connected" | "passed" | "passed_free_path" | "passed_safe_path" => (1..7).contains(&index),
+        "mobility" => index != King as usize && index != Pawn as usize,
+        _ => true,
+    }
+}
+
+fn sigmoid(score: i32, k: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-k * score as f64 / 400.0))
+}
+
+// The positions, split across worker threads that live as long as the set, so that the
+// tuner can ask for the error of every small change without starting threads each time.
+pub struct TuningSet {
+    len: usize,
+    jobs: Vec<Sender<(Arc<EvalParams>, f64)>>,
+    results: Receiver<(usize, f64)>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl TuningSet {
+    pub fn new(mut entries: Vec<TuningEntry>) -> Self {
+        let len = entries.len();
+        let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+        let chunk_size = len.div_ceil(threads).max(1);
+        let (result_sender, results) = mpsc::channel();
+
+        let mut jobs = Vec::new();
+        let mut workers = Vec::new();
+        while !entries.is_empty() {
+            let chunk: Vec<TuningEntry> = entries.drain(..chunk_size.min(entries.len())).collect();
+            let (job_sender, job_receiver) = mpsc::channel::<(Arc<EvalParams>, f64)>();
+            let result_sender = result_sender.clone();
+            let worker = workers.len();
+
+            workers.push(thread::spawn(move || {
+                for (params, k) in job_receiver {
+                    let total = chunk
+                        .iter()
+                        .map(|entry| {
+                            let score = entry.chess.evaluate_with(White, &params);
+                            (entry.result - sigmoid(score, k)).powi(2)
+                        })
+                        .sum();
+                    if result_sender.send((worker, total)).is_err() {
+                        break;
+                    }
+                }
+            }));
+            jobs.push(job_sender);
+        }
+
+        Self {
+            len,
+            jobs,
+            results,
+            workers,
+        }
+    }
+
+    // The mean squared difference between the results and the scores mapped to
+    // expected results.
+    pub fn error(&self, params: &EvalParams, k: f64) -> f64 {
+        let params = Arc::new(params.clone());
+        for job in &self.jobs {
+            job.send((Arc::clone(&params), k)).unwrap();
+        }
+
+        // summed in the same order every time, so that equal parameters give equal errors
+        let mut totals = vec![0.0; self.jobs.len()];
+        for _ in 0..self.jobs.len() {
+            let (worker, total) = self.results.recv().unwrap();
+            totals[worker] = total;
+        }
+
+        totals.iter().sum::<f64>() / self.len.max(1) as f64
+    }
+}
+
+impl Drop for TuningSet {
+    fn drop(&mut self) {
+        // the workers stop once their jobs are gone
+        self.jobs.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+// The scaling constant that fits the current evaluation best, so that the tuning
+// changes the weights and not the scale of the scores.
+pub fn find_k(set: &TuningSet, params: &EvalParams) -> f64 {
+    let (mut low, mut high) = (0.0, 5.0);
+    for _ in 0..50 {
+        let third = (high - low) / 3.0;
+        let (a, b) = (low + third, high - third);
+        if set.error(params, a) < set.error(params, b) {
+            high = b
+        } else {
+            low = a
+        }
+    }
+    (low + high) / 2.0
+}
@@ -0,0 +1,124 @@
+use std::mem::size_of;
+
+use crate::{
+    position::Move,
+    search::{MATE, MAX_PLY},
+};
+
+pub const DEFAULT_HASH_MB: usize = 16;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: i32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+    // the search that stored the entry
+    pub age: u8,
+}
+
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    age: u8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / size_of::<Option<Entry>>()).max(1);
+        Self {
+            entries: vec![None; len],
+            age: 0,
+        }
+    }
+
+    pub fn resize(&mut self, mb: usize) {
+        *self = Self::new(mb)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.iter_mut().for_each(|entry| *entry = None)
+    }
+
+    // Entries of earlier searches no longer keep shallower results out of their slot.
+    pub fn new_search(&mut self) {
+        self.age = self.age.wrapping_add(1)
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: i32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+        ply: i32,
+    ) {
+        let index = self.index(key);
+
+        // keep the deeper result for the same position, but never lose its best move,
+        // and do not let a shallow result push out a deeper one of the current search
+        let best_move = match self.entries[index] {
+            Some(entry) if entry.key == key => {
+                if entry.depth > depth && bound != Bound::Exact {
+                    return;
+                }
+                best_move.or(entry.best_move)
+            }
+            Some(entry) if entry.age == self.age && entry.depth > depth => return,
+            _ => best_move,
+        };
+
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            bound,
+            score: score_to_tt(score, ply),
+            best_move,
+            age: self.age,
+        })
+    }
+}
+
+// Mate scores are stored relative to the node, not to the root, so that they stay
+// correct when the same position is reached at a different ply.
+fn score_to_tt(score: i32, ply: i32) -> i32 {
+    if score > MATE - MAX_PLY {
+        score + ply
+    } else if score < -MATE + MAX_PLY {
+        score - ply
+    } else {
+        score
+    }
+}
+
+pub fn score_from_tt(score: i32, ply: i32) -> i32 {
+    if score > MATE - MAX_PLY {
+        score - ply
+    } else if score < -MATE + MAX_PLY {
+        score + ply
+    } else {
+        score
+    }
+}
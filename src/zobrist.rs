@@ -0,0 +1,119 @@
+use crate::{
+    bitboard::*,
+    board::{CastleRight, Chess, Color},
+    piece::{PieceType, PIECE_TYPES},
+    position::Position,
+};
+
+const PIECE_KEYS: [[[u64; 64]; 6]; 2] = gen_piece_keys();
+const SIDE_KEY: u64 = random(1000);
+const CASTLE_KEYS: [u64; 4] = [random(1001), random(1002), random(1003), random(1004)];
+const EN_PASSANT_KEYS: [u64; 8] = gen_keys(1005);
+
+// splitmix64, evaluated at compile time so the keys are the same on every run
+const fn random(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn gen_keys<const N: usize>(seed: u64) -> [u64; N] {
+    let mut keys = [0; N];
+    let mut i = 0;
+    while i < N {
+        keys[i] = random(seed + i as u64);
+        i += 1;
+    }
+    keys
+}
+
+const fn gen_piece_keys() -> [[[u64; 64]; 6]; 2] {
+    let mut keys = [[[0; 64]; 6]; 2];
+    let mut color = 0;
+    while color < 2 {
+        let mut piece_type = 0;
+        while piece_type < 6 {
+            keys[color][piece_type] = gen_keys(2000 + (color * 6 + piece_type) as u64 * 64);
+            piece_type += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+pub fn piece_key(piece_type: PieceType, color: Color, pos: Position) -> u64 {
+    PIECE_KEYS[color as usize][piece_type as usize][usize::from(pos)]
+}
+
+pub fn side_key() -> u64 {
+    SIDE_KEY
+}
+
+pub fn castle_key(white_castle: CastleRight, black_castle: CastleRight) -> u64 {
+    let rights = [
+        white_castle.can_kingside_castle(),
+        white_castle.can_queenside_castle(),
+        black_castle.can_kingside_castle(),
+        black_castle.can_queenside_castle(),
+    ];
+
+    rights
+        .iter()
+        .zip(CASTLE_KEYS.iter())
+        .filter(|(&right, _)| right)
+        .fold(0, |key, (_, &castle_key)| key ^ castle_key)
+}
+
+// The en passant file is only hashed when a pawn can actually capture there, so that
+// positions reached through a double push and through a single push hash the same.
+pub fn en_passant_key(chess: &Chess) -> u64 {
+    match chess.en_passant {
+        Some(en_passant) => {
+            let capturer = if en_passant.row == 5 {
+                Color::Black
+            } else {
+                Color::White
+            };
+            let attackers = pawn_attacks(!capturer, usize::from(en_passant))
+                & chess.pieces_color(PieceType::Pawn, capturer);
+
+            if attackers != EMPTY {
+                EN_PASSANT_KEYS[en_passant.col as usize]
+            } else {
+                0
+            }
+        }
+        None => 0,
+    }
+}
+
+pub fn compute_hash(chess: &Chess) -> u64 {
+    let mut hash = 0;
+
+    for &color in &[Color::White, Color::Black] {
+        for &piece_type in &PIECE_TYPES {
+            for sq in squares(chess.pieces_color(piece_type, color)) {
+                hash ^= piece_key(piece_type, color, sq.into())
+            }
+        }
+    }
+
+    if chess.turn == Color::Black {
+        hash ^= SIDE_KEY
+    }
+
+    hash ^ castle_key(chess.white_castle, chess.black_castle) ^ en_passant_key(chess)
+}
+
+pub fn compute_pawn_hash(chess: &Chess) -> u64 {
+    let mut hash = 0;
+
+    for &color in &[Color::White, Color::Black] {
+        for sq in squares(chess.pieces_color(PieceType::Pawn, color)) {
+            hash ^= piece_key(PieceType::Pawn, color, sq.into())
+        }
+    }
+
+    hash
+}
@@ -0,0 +1,88 @@
+use std::convert::TryInto;
+
+use chess_engine2::{Chess, SearchLimits};
+
+fn play(chess: &mut Chess, moves: &[&str]) {
+    for &r#move in moves {
+        chess.set(r#move.try_into().unwrap())
+    }
+}
+
+#[test]
+fn repetition() {
+    let mut chess = Chess::new();
+    assert!(!chess.is_repetition());
+
+    play(&mut chess, &["g1f3", "g8f6", "f3g1"]);
+    assert!(!chess.is_repetition());
+
+    play(&mut chess, &["f6g8"]);
+    assert!(chess.is_repetition());
+    assert!(!chess.is_threefold_repetition());
+
+    play(&mut chess, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert!(chess.is_threefold_repetition());
+
+    // a pawn move makes the earlier positions unreachable
+    let mut chess = Chess::new();
+    play(
+        &mut chess,
+        &["g1f3", "g8f6", "f3g1", "f6g8", "e2e4", "e7e5"],
+    );
+    play(&mut chess, &["g1f3", "g8f6", "f3g1"]);
+    assert!(!chess.is_repetition());
+    play(&mut chess, &["f6g8"]);
+    assert!(chess.is_repetition());
+
+    let undo = chess.make_move("g1f3".try_into().unwrap());
+    chess.unmake_move("g1f3".try_into().unwrap(), undo);
+    assert!(chess.is_repetition());
+}
+
+#[test]
+fn fifty_moves() {
+    let mut chess = Chess::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    assert!(!chess.is_fifty_move_draw());
+
+    play(&mut chess, &["a1a2"]);
+    assert!(chess.is_fifty_move_draw());
+
+    // checkmate takes precedence
+    let chess = Chess::from_fen("R3k3/8/4K3/8/8/8/8/8 b - - 100 80").unwrap();
+    assert!(!chess.is_fifty_move_draw());
+}
+
+#[test]
+fn insufficient_material() {
+    for &(fen, is_draw) in &[
+        ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+        ("4kb2/8/8/8/8/8/8/4K3 w - - 0 1", true),
+        ("2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1", true),
+        ("4kb2/8/8/8/8/8/8/4KB2 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1", false),
+        ("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", false),
+    ] {
+        let chess = Chess::from_fen(fen).unwrap();
+        assert_eq!(chess.is_insufficient_material(), is_draw, "{}", fen);
+    }
+}
+
+#[test]
+fn search_takes_repetition_when_losing() {
+    let limits = SearchLimits {
+        depth: Some(3),
+        ..SearchLimits::default()
+    };
+
+    let chess = Chess::from_fen("k7/8/8/8/8/8/q7/6NK w - - 0 1").unwrap();
+    assert!(chess.search(&limits).score < -500);
+
+    let mut chess = chess;
+    play(&mut chess, &["g1f3", "a2b2", "f3g1", "b2a2"]);
+    let result = chess.search(&limits);
+    assert_eq!(result.best_move.map(String::from).as_deref(), Some("g1f3"));
+    assert_eq!(result.score, 0);
+}
@@ -0,0 +1,164 @@
+use std::convert::TryInto;
+
+use chess_engine2::{
+    Chess, Color::*, PawnTable, Position, Score, Term, A2, A3, B2, DEFAULT_PARAMS, H2, MAX_PHASE,
+    TERMS,
+};
+
+fn eval(fen: &str) -> i32 {
+    Chess::from_fen(fen).unwrap().evaluate(White)
+}
+
+#[test]
+fn taper() {
+    let score = Score::new(100, 20);
+    assert_eq!(score.taper(MAX_PHASE), 100);
+    assert_eq!(score.taper(0), 20);
+    assert_eq!(score.taper(MAX_PHASE / 2), 60);
+    assert_eq!(score.taper(MAX_PHASE + 4), 100);
+}
+
+#[test]
+fn phase() {
+    assert_eq!(Chess::new().phase, MAX_PHASE);
+    assert_eq!(
+        Chess::from_fen("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1")
+            .unwrap()
+            .phase,
+        0
+    );
+    assert_eq!(
+        Chess::from_fen("3qk3/8/8/8/8/8/8/2R1K1N1 w - - 0 1")
+            .unwrap()
+            .phase,
+        7
+    );
+}
+
+#[test]
+fn symmetric() {
+    let chess = Chess::new();
+    assert_eq!(chess.evaluate(White), 0);
+    assert_eq!(chess.evaluate(Black), 0);
+
+    // the same position with the colors swapped
+    let white = eval("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    let black = eval("r3k2r/pppbbppp/2n2q1P/1P2p3/3pn3/BN2PNP1/P1PPQPB1/R3K2R b KQkq - 0 1");
+    assert_eq!(white, -black);
+}
+
+#[test]
+fn incremental() {
+    let mut chess = Chess::new();
+    for &r#move in &[
+        "e2e4", "d7d5", "e4d5", "g8f6", "f1b5", "c7c6", "d5c6", "d8d2", "b1d2", "e8d8", "c6b7",
+        "h7h6", "b7a8q", "h6h5", "g1f3", "h5h4", "e1g1",
+    ] {
+        chess.try_play(r#move.try_into().unwrap()).unwrap();
+        let fresh = Chess::from_fen(&chess.to_fen()).unwrap();
+        assert_eq!(chess.evaluate(White), fresh.evaluate(White));
+        assert_eq!(chess.phase, fresh.phase);
+    }
+}
+
+#[test]
+fn king_centralization_in_endgames() {
+    // with all the pieces on the board the king is safer at home, without pieces it belongs in the center
+    assert!(
+        eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1")
+            > eval("rnbqkbnr/pppppppp/8/8/3K4/8/PPPPPPPP/RNBQ1BNR w - - 0 1")
+    );
+    assert!(eval("4k3/8/8/8/3K4/8/4P3/8 w - - 0 1") > eval("4k3/8/8/8/8/8/4P3/7K w - - 0 1"));
+}
+
+#[test]
+fn passed_pawns() {
+    let mut pawn_table = PawnTable::new();
+
+    let chess = Chess::from_fen("4k3/8/8/3p4/8/8/PP1PP2P/4K3 w - - 0 1").unwrap();
+    let entry = pawn_table.probe(&chess, &DEFAULT_PARAMS);
+    let passed = |squares: &[Position]| {
+        squares
+            .iter()
+            .fold(0, |bb, &pos| bb | 1 << usize::from(pos))
+    };
+    assert_eq!(entry.passed[White as usize], passed(&[A2, B2, H2]));
+    assert_eq!(entry.passed[Black as usize], passed(&[]));
+
+    // only the front pawn of a doubled pair can be passed
+    let chess = Chess::from_fen("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1").unwrap();
+    assert_eq!(
+        pawn_table.probe(&chess, &DEFAULT_PARAMS).passed[White as usize],
+        passed(&[A3])
+    );
+}
+
+#[test]
+fn pawn_structure() {
+    // doubled and isolated against two connected pawns
+    assert!(eval("4k3/8/8/8/8/8/PP6/4K3 w - - 0 1") > eval("4k3/8/8/8/8/P7/P7/4K3 w - - 0 1"));
+    assert!(
+        eval("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1")
+            > eval("4k3/pppppppp/8/8/8/2P1P3/PP1P1P1P/4K3 w - - 0 1")
+    );
+
+    let mut pawn_table = PawnTable::new();
+    let chess = Chess::from_fen("4k3/pp4pp/2p5/3p4/3P4/2P1P3/PP4PP/4K3 w - - 0 1").unwrap();
+    let entry = pawn_table.probe(&chess, &DEFAULT_PARAMS);
+    assert_eq!(entry.key, chess.pawn_hash);
+    assert_eq!(pawn_table.probe(&chess, &DEFAULT_PARAMS).score, entry.score);
+}
+
+#[test]
+fn king_safety() {
+    let castled = "r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1PPP/R1BQ1RK1 w - - 0 1";
+    assert_eq!(eval(castled), 0);
+
+    // the same pawns are missing on both sides, but only the white king lost its shelter
+    let white_exposed = "r1bq1rk1/2pp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1P2/R1BQ1RK1 w - - 0 1";
+    let black_exposed = "r1bq1rk1/pppp1p2/2n2n2/2b1p3/2B1P3/2N2N2/2PP1PPP/R1BQ1RK1 w - - 0 1";
+    assert!(eval(white_exposed) < 0);
+    assert_eq!(eval(white_exposed), -eval(black_exposed));
+}
+
+#[test]
+fn piece_activity() {
+    // a bishop blocked by its own pawn, and a rook behind one
+    assert!(eval("4k3/8/8/8/8/8/P7/B3K3 w - - 0 1") > eval("4k3/8/8/8/8/8/1P6/B3K3 w - - 0 1"));
+    assert!(
+        eval("6k1/5ppp/8/8/8/8/2P2PPP/3R2K1 w - - 0 1")
+            > eval("6k1/5ppp/8/8/8/8/3P1PPP/3R2K1 w - - 0 1")
+    );
+
+    // the bishop pair against bishop and knight
+    assert_eq!(eval("2bqkb2/8/8/8/8/8/8/2BQKB2 w - - 0 1"), 0);
+    assert!(eval("2bqkn2/8/8/8/8/8/8/2BQKB2 w - - 0 1") > 0);
+    assert!(eval("2nqkb2/8/8/8/8/8/8/2BQKB2 w - - 0 1") > 0);
+
+    // trapped pieces
+    assert!(eval("4k3/Bp6/8/8/8/8/8/4K3 w - - 0 1") > eval("4k3/B7/1p6/8/8/8/8/4K3 w - - 0 1"));
+    assert!(eval("4k3/8/8/8/8/8/5PPP/5RK1 w - - 0 1") > eval("4k3/8/8/8/8/8/5PPP/6KR w - - 0 1"));
+}
+
+#[test]
+fn trace() {
+    for fen in &[
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bq1rk1/2pp1ppp/2n2n2/2b1p3/2B1P3/2N2N2/PPPP1P2/R1BQ1RK1 w - - 0 1",
+        "4k3/8/8/3p4/8/8/PP1PP2P/4K3 b - - 0 1",
+    ] {
+        let chess = Chess::from_fen(fen).unwrap();
+        let trace = chess.evaluate_trace();
+        assert_eq!(trace.total(), chess.evaluate(White));
+        assert_eq!(trace.phase, chess.phase);
+    }
+
+    let trace = Chess::new().evaluate_trace();
+    assert_eq!(
+        trace.terms[Term::Material as usize][0],
+        trace.terms[Term::Material as usize][1]
+    );
+    assert!(TERMS
+        .iter()
+        .all(|&term| trace.term(term) == Score::default()));
+}
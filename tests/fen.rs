@@ -0,0 +1,140 @@
+use std::convert::TryInto;
+
+use chess_engine2::{Chess, Color, PieceType};
+
+#[test]
+fn round_trip() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 37 112",
+        "4k3/8/8/8/8/8/8/4K2R b K - 99 200",
+    ];
+
+    for &fen in &fens {
+        assert_eq!(Chess::from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn clocks_follow_moves() {
+    let mut chess = Chess::new();
+    let moves = ["g1f3", "g8f6", "b1c3", "e7e5", "f3e5"];
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/5N2/PPPPPPPP/RNBQKB1R b KQkq - 1 1",
+        "rnbqkb1r/pppppppp/5n2/8/8/5N2/PPPPPPPP/RNBQKB1R w KQkq - 2 2",
+        "rnbqkb1r/pppppppp/5n2/8/8/2N2N2/PPPPPPPP/R1BQKB1R b KQkq - 3 2",
+        "rnbqkb1r/pppp1ppp/5n2/4p3/8/2N2N2/PPPPPPPP/R1BQKB1R w KQkq e6 0 3",
+        "rnbqkb1r/pppp1ppp/5n2/4N3/8/2N5/PPPPPPPP/R1BQKB1R b KQkq - 0 3",
+    ];
+
+    for (&r#move, &fen) in moves.iter().zip(fens.iter()) {
+        chess.set(r#move.try_into().unwrap());
+        assert_eq!(chess.to_fen(), fen);
+    }
+}
+
+#[test]
+fn rejects_invalid() {
+    use chess_engine2::{Color::*, FenError::*};
+
+    let cases = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0",
+            WrongFieldCount(5),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            WrongRankCount(7),
+        ),
+        (
+            "rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            BadRankLength(7),
+        ),
+        (
+            "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            InvalidPiece('9'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+            InvalidPiece('X'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR white KQkq - 0 1",
+            InvalidSide("white".into()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1",
+            InvalidCastling("KQkx".into()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+            InconsistentCastling('K'),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e9 0 1",
+            InvalidEnPassant("e9".into()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1",
+            ImpossibleEnPassant("e6".into()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+            InvalidHalfmoveClock("x".into()),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+            InvalidFullmoveNumber("0".into()),
+        ),
+        (
+            "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+            MissingKing(Black),
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBKKBNR w kq - 0 1",
+            TooManyKings(White),
+        ),
+        (
+            "rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w KQq - 0 1",
+            PawnOnBackRank("h8".into()),
+        ),
+        ("4k3/8/8/8/8/8/8/4RK2 w - - 0 1", SideNotToMoveInCheck),
+    ];
+
+    for (fen, error) in cases.iter() {
+        assert_eq!(Chess::from_fen(fen).err().as_ref(), Some(error), "{}", fen);
+    }
+}
+
+#[test]
+fn lenient_mode() {
+    let chess =
+        Chess::from_fen_lenient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq e6").unwrap();
+    assert_eq!(
+        chess.to_fen(),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w Qkq - 0 1"
+    );
+
+    assert!(Chess::from_fen_lenient("8/8/8/8/8/8/8/8 w - -").is_ok());
+    assert!(Chess::from_fen_lenient("rnbqkbnr/ppppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - -").is_err());
+}
+
+#[test]
+fn board() {
+    let board = Chess::new().board();
+
+    assert_eq!(board.iter().flatten().count(), 32);
+    assert_eq!(
+        board[0].map(|piece| (piece.r#type, piece.color)),
+        Some((PieceType::Rook, Color::Black))
+    );
+    assert_eq!(
+        board[60].map(|piece| (piece.r#type, piece.color)),
+        Some((PieceType::King, Color::White))
+    );
+    assert_eq!(board[36], None);
+}
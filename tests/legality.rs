@@ -0,0 +1,78 @@
+use std::convert::{TryFrom, TryInto};
+
+use chess_engine2::{Chess, IllegalMove, Move};
+
+fn r#move(r#move: &str) -> Move {
+    r#move.try_into().unwrap()
+}
+
+#[test]
+fn plays_legal_moves() {
+    let mut chess = Chess::new();
+    chess.try_play(r#move("e2e4")).unwrap();
+    chess.try_play(r#move("e7e5")).unwrap();
+    assert_eq!(
+        chess.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2"
+    );
+}
+
+#[test]
+fn rejects_illegal_moves() {
+    let cases = [
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e2e5",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e7e5",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e3e4",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e1g1",
+        ),
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e2e4q",
+        ),
+        // castling through check and out of check
+        ("4k3/8/8/8/8/8/5r2/4K2R w K - 0 1", "e1g1"),
+        ("4k3/8/8/8/8/8/4r3/4K2R w K - 0 1", "e1g1"),
+        // a pinned piece and a king walking into check
+        ("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1", "e2c3"),
+        ("4k3/8/8/8/8/8/3r4/4K3 w - - 0 1", "e1d1"),
+        // promotions need a piece, and only promotions take one
+        ("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8"),
+        ("4k3/8/P7/8/8/8/8/4K3 w - - 0 1", "a6a7q"),
+    ];
+
+    for &(fen, uci) in &cases {
+        let mut chess = Chess::from_fen(fen).unwrap();
+        let r#move = r#move(uci);
+        assert_eq!(chess.try_play(r#move), Err(IllegalMove(r#move)), "{}", uci);
+        assert_eq!(chess.to_fen(), fen);
+        assert_eq!(chess.hash, chess.compute_hash());
+        assert!(chess.history.is_empty());
+    }
+}
+
+#[test]
+fn rejects_malformed_moves() {
+    for &uci in &[
+        "", "e2", "e2e", "e2e4qq", "i2i4", "e9e4", "e2e4x", "é2e4", "a7a8k",
+    ] {
+        assert!(Move::try_from(uci).is_err(), "{}", uci);
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "no piece of the side to move")]
+fn make_move_needs_a_piece() {
+    Chess::new().make_move(r#move("e4e5"));
+}
@@ -0,0 +1,19 @@
+use std::convert::TryFrom;
+
+use chess_engine2::{Move, PieceType};
+
+#[test]
+fn promotion_letters() {
+    for &(uci, piece_type) in &[
+        ("a7a8q", PieceType::Queen),
+        ("a7a8r", PieceType::Rook),
+        ("a7a8b", PieceType::Bishop),
+        ("a7a8n", PieceType::Knight),
+    ] {
+        let r#move = Move::try_from(uci).unwrap();
+        assert_eq!(r#move.prom, Some(piece_type), "{}", uci);
+        assert_eq!(String::from(r#move), uci);
+    }
+
+    assert!(Move::try_from("a7a8k").is_err());
+}
@@ -0,0 +1,60 @@
+use std::convert::TryInto;
+
+use chess_engine2::{Chess, Color::*, Outcome};
+
+fn play(chess: &mut Chess, moves: &[&str]) {
+    for &r#move in moves {
+        chess.set(r#move.try_into().unwrap())
+    }
+}
+
+#[test]
+fn checkmate_and_stalemate() {
+    let mut chess = Chess::new();
+    assert_eq!(chess.outcome(), None);
+    assert!(!chess.is_game_over());
+
+    play(
+        &mut chess,
+        &["e2e4", "e7e5", "f1c4", "b8c6", "d1h5", "g8f6", "h5f7"],
+    );
+    assert_eq!(chess.outcome(), Some(Outcome::Checkmate(White)));
+    assert_eq!(chess.outcome().unwrap().result(), "1-0");
+    assert!(chess.is_game_over());
+
+    let mut chess = Chess::new();
+    play(&mut chess, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+    assert_eq!(chess.outcome(), Some(Outcome::Checkmate(Black)));
+    assert_eq!(chess.outcome().unwrap().result(), "0-1");
+
+    let chess = Chess::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert_eq!(chess.outcome(), Some(Outcome::Stalemate));
+    assert_eq!(chess.outcome().unwrap().winner(), None);
+    assert_eq!(chess.outcome().unwrap().result(), "1/2-1/2");
+}
+
+#[test]
+fn draws() {
+    let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    let mut chess = Chess::new();
+    play(&mut chess, &shuffle);
+    assert_eq!(chess.outcome(), None);
+    play(&mut chess, &shuffle);
+    assert_eq!(chess.outcome(), Some(Outcome::ThreefoldRepetition));
+    play(&mut chess, &shuffle);
+    play(&mut chess, &shuffle);
+    assert_eq!(chess.outcome(), Some(Outcome::FivefoldRepetition));
+
+    let fifty = Chess::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 100 80").unwrap();
+    assert_eq!(fifty.outcome(), Some(Outcome::FiftyMoves));
+    let seventy_five = Chess::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 150 80").unwrap();
+    assert_eq!(seventy_five.outcome(), Some(Outcome::SeventyFiveMoves));
+
+    let material = Chess::from_fen("4k3/8/8/8/8/8/8/4KB2 w - - 0 1").unwrap();
+    assert_eq!(material.outcome(), Some(Outcome::InsufficientMaterial));
+
+    // mate on the hundredth halfmove is still mate
+    let mate = Chess::from_fen("R3k3/8/4K3/8/8/8/8/8 b - - 100 80").unwrap();
+    assert_eq!(mate.outcome(), Some(Outcome::Checkmate(White)));
+}
@@ -0,0 +1,67 @@
+use chess_engine2::{Chess, Color::White, EvalParams, Score, Trace, DEFAULT_PARAMS};
+
+#[test]
+fn round_trip() {
+    let text = DEFAULT_PARAMS.to_string();
+    assert!(text.starts_with("material 0 0 "));
+    assert_eq!(text.parse::<EvalParams>().unwrap(), DEFAULT_PARAMS);
+
+    let mut params = EvalParams {
+        bishop_pair: Score::new(1, -2),
+        ..EvalParams::default()
+    };
+    params.psqt[4][27] = Score::new(-50, 50);
+    assert_eq!(params.to_string().parse::<EvalParams>().unwrap(), params);
+
+    let path = std::env::temp_dir().join("chess_engine2_params_round_trip.txt");
+    params.save(&path).unwrap();
+    assert_eq!(EvalParams::load(&path).unwrap(), params);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn partial_files() {
+    let params: EvalParams = "# tweaks\n\nbishop_pair 40 60\n  doubled -5 -5\n"
+        .parse()
+        .unwrap();
+    assert_eq!(params.bishop_pair, Score::new(40, 60));
+    assert_eq!(params.doubled, Score::new(-5, -5));
+    assert_eq!(params.material, DEFAULT_PARAMS.material);
+
+    assert!("bishop_pairs 40 60".parse::<EvalParams>().is_err());
+    assert!("bishop_pair 40".parse::<EvalParams>().is_err());
+    assert!("bishop_pair 40 x".parse::<EvalParams>().is_err());
+    assert!("material 0 0 900 900".parse::<EvalParams>().is_err());
+}
+
+#[test]
+fn changes_evaluation() {
+    let chess = Chess::from_fen("3k4/8/8/8/8/8/8/Q3K3 w - - 0 1").unwrap();
+    let default = Trace::new(&chess, &DEFAULT_PARAMS).total();
+    assert_eq!(default, chess.evaluate(White));
+
+    let mut params = EvalParams::default();
+    params.material[1] += Score::new(100, 100);
+    assert_eq!(Trace::new(&chess, &params).total(), default + 100);
+    assert_eq!(chess.evaluate_with(White, &params), default + 100);
+
+    // Black uses the same tables, mirrored
+    params.psqt[0][usize::from(chess_engine2::D8) ^ 56] += Score::new(30, 30);
+    assert_eq!(Trace::new(&chess, &params).total(), default + 70);
+}
+
+#[test]
+fn fields_match() {
+    let mut params = EvalParams::default();
+    let len = params.fields().len();
+    for field in 0..len {
+        params.field_mut(field)[0] = Score::new(field as i32, -1);
+    }
+
+    for (field, (name, scores)) in params.fields().into_iter().enumerate() {
+        assert_eq!(scores[0], Score::new(field as i32, -1), "{}", name);
+        assert_eq!(scores.len(), DEFAULT_PARAMS.field(field).len(), "{}", name);
+    }
+    assert_eq!(params.bishop_pair, Score::new(22, -1));
+    assert_eq!(params.psqt[5][0], Score::new(6, -1));
+}
@@ -0,0 +1,82 @@
+use chess_engine2::Chess;
+
+fn perft(fen: &str, depth: u32, nodes: u64) {
+    let chess = Chess::from_fen(fen).unwrap();
+    assert_eq!(chess.perft(depth), nodes, "perft({}) of {}", depth, fen);
+}
+
+#[test]
+fn startpos() {
+    let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    perft(fen, 1, 20);
+    perft(fen, 2, 400);
+    perft(fen, 3, 8902);
+    perft(fen, 4, 197281);
+}
+
+#[test]
+fn kiwipete() {
+    let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    perft(fen, 1, 48);
+    perft(fen, 2, 2039);
+    perft(fen, 3, 97862);
+}
+
+#[test]
+fn en_passant_and_pins() {
+    let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+    perft(fen, 1, 14);
+    perft(fen, 2, 191);
+    perft(fen, 3, 2812);
+    perft(fen, 4, 43238);
+}
+
+#[test]
+fn promotions_and_castling() {
+    let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+    perft(fen, 1, 6);
+    perft(fen, 2, 264);
+    perft(fen, 3, 9467);
+
+    let mirrored = "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1";
+    perft(mirrored, 1, 6);
+    perft(mirrored, 2, 264);
+    perft(mirrored, 3, 9467);
+}
+
+#[test]
+fn discovered_checks_and_promotions() {
+    let fen = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+    perft(fen, 1, 44);
+    perft(fen, 2, 1486);
+    perft(fen, 3, 62379);
+}
+
+#[test]
+fn middlegame() {
+    let fen = "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10";
+    perft(fen, 1, 46);
+    perft(fen, 2, 2079);
+    perft(fen, 3, 89890);
+}
+
+#[test]
+fn edge_cases() {
+    // en passant capture that would expose the king
+    perft("3k4/3p4/8/K1P4r/8/8/8/8 b - - 0 1", 6, 1134888);
+    perft("8/8/4k3/8/2p5/8/B2P2K1/8 w - - 0 1", 6, 1015133);
+    perft("8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1", 6, 1440467);
+    // castling rights lost by capture, castling through check
+    perft("5k2/8/8/8/8/8/8/4K2R w K - 0 1", 6, 661072);
+    perft("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", 6, 803711);
+    perft("r3k2r/1b4bq/8/8/8/8/7B/R3K2R w KQkq - 0 1", 4, 1274206);
+    perft("r3k2r/8/3Q4/8/8/5q2/8/R3K2R b KQkq - 0 1", 4, 1720476);
+    // promotions, underpromotions to give check
+    perft("2K2r2/4P3/8/8/8/8/8/3k4 w - - 0 1", 6, 3821001);
+    perft("8/P1k5/K7/8/8/8/8/8 w - - 0 1", 6, 92683);
+    perft("K1k5/8/P7/8/8/8/8/8 w - - 0 1", 6, 2217);
+    perft("8/k1P5/8/1K6/8/8/8/8 w - - 0 1", 7, 567584);
+    // stalemate and checkmate
+    perft("8/8/2k5/5q2/5n2/8/5K2/8 b - - 0 1", 4, 23527);
+    perft("8/8/1P2K3/8/2n5/1q6/8/5k2 b - - 0 1", 5, 1004658);
+}
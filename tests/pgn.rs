@@ -0,0 +1,132 @@
+use chess_engine2::{Chess, Game, GameNode};
+
+const PGN: &str = r#"
+[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[Annotator "Someone \"quoted\""]
+
+{Opening comment.} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.}
+3... a6 (3... Nf6 4. O-O (4. d3 $1) 4... Nxe4) 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6
+8. c3 O-O 9. h3 Nb8 10. d4 Nbd7 11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4
+15. Nb1 h6 16. Bh4 c5 17. dxe5 Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6
+21. Nc4 Nxc4 22. Bxc4 Nb6 23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7
+27. Qe3 Qg5 28. Qxg5 hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5
+33. f3 Bc8 34. Kf2 Bf5 35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3
+39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6 Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+% escaped line 1. d4
+[Event "Second"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+[SetUp "1"]
+
+1.e4!? Kd7 ; rest of line comment
+2. Kf2?! (2. e5 Ke6 (2... Kc6 {nested}) 3. Ke2) 2... Ke6 *
+"#;
+
+#[test]
+fn parses_games() {
+    let games = Game::parse_all(PGN).unwrap();
+    assert_eq!(games.len(), 2);
+
+    let fischer = &games[0];
+    assert_eq!(fischer.tag("White"), Some("Fischer, Robert J."));
+    assert_eq!(fischer.tag("Annotator"), Some("Someone \"quoted\""));
+    assert_eq!(fischer.result, "1/2-1/2");
+    assert_eq!(fischer.comment.as_deref(), Some("Opening comment."));
+    assert_eq!(fischer.moves.len(), 85);
+    assert_eq!(
+        fischer.moves[4].comment.as_deref(),
+        Some("This opening is called the Ruy Lopez.")
+    );
+
+    let variation = &fischer.moves[5].variations[0];
+    assert_eq!(variation.len(), 3);
+    assert_eq!(String::from(variation[1].r#move), "e1g1");
+    assert_eq!(variation[1].variations[0][0].nags, vec![1]);
+    assert_eq!(
+        fischer.end_position().to_fen(),
+        "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43"
+    );
+
+    let second = &games[1];
+    assert_eq!(second.start.to_fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    assert_eq!(second.result, "*");
+    assert_eq!(second.mainline().len(), 4);
+    assert_eq!(second.moves[0].nags, vec![5]);
+    assert_eq!(
+        second.moves[1].comment.as_deref(),
+        Some("rest of line comment")
+    );
+    assert_eq!(second.moves[2].nags, vec![6]);
+    assert_eq!(
+        second.moves[2].variations[0][1].variations[0][0]
+            .comment
+            .as_deref(),
+        Some("nested")
+    );
+}
+
+#[test]
+fn round_trip() {
+    let games = Game::parse_all(PGN).unwrap();
+    let pgn: Vec<String> = games.iter().map(|game| game.to_pgn().unwrap()).collect();
+    let pgn = pgn.join("\n");
+    assert!(pgn.lines().all(|line| line.len() < 80));
+
+    let words: Vec<&str> = pgn.split_whitespace().collect();
+    let words = words.join(" ");
+    assert!(words.contains("3... a6 (3... Nf6 4. O-O (4. d3 $1) 4... Nxe4) 4. Ba4"));
+    assert!(words.contains(
+        "1. e4 $5 Kd7 {rest of line comment} 2. Kf2 $6 (2. e5 Ke6 (2... Kc6 {nested}) 3. Ke2) 2... Ke6 *"
+    ));
+
+    let reparsed = Game::parse_all(&pgn).unwrap();
+    assert_eq!(reparsed.len(), games.len());
+    for (game, reparsed) in games.iter().zip(&reparsed) {
+        assert_eq!(game.moves, reparsed.moves);
+        assert_eq!(game.to_pgn().unwrap(), reparsed.to_pgn().unwrap());
+    }
+}
+
+#[test]
+fn rejects_invalid() {
+    assert!(Game::parse_all("1. e4 e5 2. Ke3 *").is_err());
+    assert!(Game::parse_all("1. e4 (1. d4 *").is_err());
+    assert!(Game::parse_all("1. e4 ) *").is_err());
+    assert!(Game::parse_all("1. e4 {unterminated").is_err());
+    assert!(Game::parse_all("[Event \"x\" 1. e4").is_err());
+}
+
+#[test]
+fn move_numbers_without_dots() {
+    let games = Game::parse_all("12 e4 e5 13 Nf3 13... Nc6 14 . Bb5 *").unwrap();
+    let moves: Vec<String> = games[0].mainline().into_iter().map(String::from).collect();
+    assert_eq!(moves, vec!["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]);
+}
+
+#[test]
+fn writes_setup_tags() {
+    let mut game = Game::new();
+    game.start = Chess::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 3 20").unwrap();
+    let r#move = game.start.parse_san("Kd7").unwrap();
+    game.moves.push(GameNode::new(r#move));
+
+    let pgn = game.to_pgn().unwrap();
+    assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/4K3 b - - 3 20\"]\n"));
+    assert!(pgn.contains("20... Kd7 *"));
+
+    let reparsed = &Game::parse_all(&pgn).unwrap()[0];
+    assert_eq!(reparsed.start.to_fen(), game.start.to_fen());
+    assert_eq!(reparsed.moves, game.moves);
+    assert_eq!(reparsed.to_pgn().unwrap(), pgn);
+
+    // stale setup tags are not written for the initial position
+    let mut game = Game::new();
+    game.set_tag("FEN", "4k3/8/8/8/8/8/4P3/4K3 b - - 3 20");
+    assert!(!game.to_pgn().unwrap().contains("FEN"));
+}
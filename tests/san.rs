@@ -0,0 +1,92 @@
+use std::convert::TryInto;
+
+use chess_engine2::Chess;
+
+fn san(fen: &str, r#move: &str) -> String {
+    let chess = Chess::from_fen(fen).unwrap();
+    chess.move_to_san(r#move.try_into().unwrap()).unwrap()
+}
+
+#[test]
+fn formats_moves() {
+    let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    assert_eq!(san(start, "e2e4"), "e4");
+    assert_eq!(san(start, "g1f3"), "Nf3");
+
+    let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+    assert_eq!(san(kiwipete, "e1g1"), "O-O");
+    assert_eq!(san(kiwipete, "e1c1"), "O-O-O");
+    assert_eq!(san(kiwipete, "d5e6"), "dxe6");
+    assert_eq!(san(kiwipete, "e2a6"), "Bxa6");
+    assert_eq!(san(kiwipete, "f3f6"), "Qxf6");
+    assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+
+    // disambiguation by file, by rank and by both
+    assert_eq!(san("4k3/8/8/8/8/8/4K3/R6R w - - 0 1", "a1d1"), "Rad1");
+    assert_eq!(san("4k3/R7/8/8/8/8/8/R3K3 w - - 0 1", "a1a4"), "R1a4");
+    assert_eq!(san("k7/8/8/8/8/2Q1Q3/8/2Q3K1 w - - 0 1", "c3d2"), "Qc3d2");
+
+    assert_eq!(san("8/P3k3/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), "a8=Q");
+    assert_eq!(san("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7b8n"), "axb8=N");
+    assert_eq!(san("6k1/5ppp/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8#");
+    assert_eq!(san("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), "exd6");
+}
+
+#[test]
+fn parses_moves() {
+    let kiwipete =
+        Chess::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    for &(san, uci) in &[
+        ("O-O", "e1g1"),
+        ("0-0-0", "e1c1"),
+        ("dxe6", "d5e6"),
+        ("Bxa6", "e2a6"),
+        ("Qxf6", "f3f6"),
+        ("Qxf6+", "f3f6"),
+        ("Nxf7!?", "e5f7"),
+        ("gxh3", "g2h3"),
+    ] {
+        assert_eq!(
+            String::from(kiwipete.parse_san(san).unwrap()),
+            uci,
+            "{}",
+            san
+        );
+    }
+
+    let promotion = Chess::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    for &(san, uci) in &[
+        ("a8=Q", "a7a8q"),
+        ("a8Q", "a7a8q"),
+        ("axb8=N", "a7b8n"),
+        ("axb8r", "a7b8r"),
+    ] {
+        assert_eq!(
+            String::from(promotion.parse_san(san).unwrap()),
+            uci,
+            "{}",
+            san
+        );
+    }
+
+    let rooks = Chess::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+    assert!(rooks.parse_san("Rf1").is_err());
+    assert_eq!(String::from(rooks.parse_san("Rhf1").unwrap()), "h1f1");
+
+    assert!(promotion.parse_san("a8").is_err());
+    assert!(promotion.parse_san("Nf3").is_err());
+    assert!(promotion.parse_san("O-O").is_err());
+    assert!(promotion.parse_san("xyz").is_err());
+}
+
+#[test]
+fn round_trip() {
+    let chess =
+        Chess::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    for r#move in chess.gen_legal_moves() {
+        let san = chess.move_to_san(r#move).unwrap();
+        assert_eq!(chess.parse_san(&san).unwrap(), r#move, "{}", san);
+    }
+}
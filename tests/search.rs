@@ -0,0 +1,268 @@
+use std::{
+    sync::atomic::Ordering,
+    thread,
+    time::{Duration, Instant},
+};
+
+use chess_engine2::{
+    Chess,
+    Color::{Black, White},
+    SearchLimits, SearchOptions, SearchResult, Searcher, MATE, MAX_PLY,
+};
+
+fn search(fen: &str, depth: i32) -> chess_engine2::SearchResult {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        ..SearchLimits::default()
+    };
+    Chess::from_fen(fen).unwrap().search(&limits)
+}
+
+#[test]
+fn finds_mate_in_one() {
+    let result = search("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 3);
+
+    assert_eq!(result.best_move.map(String::from).as_deref(), Some("a1a8"));
+    assert_eq!(result.mate_in(), Some(1));
+    assert_eq!(result.pv.first(), result.best_move.as_ref());
+    assert_eq!(result.depth, 3);
+    assert!(result.nodes > 0);
+}
+
+#[test]
+fn pv_is_playable() {
+    let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+    let result = search(fen, 4);
+
+    let mut chess = Chess::from_fen(fen).unwrap();
+    for &r#move in &result.pv {
+        assert!(chess.gen_legal_moves().contains(&r#move));
+        chess.set(r#move);
+    }
+}
+
+#[test]
+fn options_keep_mates() {
+    let chess =
+        Chess::from_fen("r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 1")
+            .unwrap();
+    let limits = SearchLimits {
+        depth: Some(5),
+        ..SearchLimits::default()
+    };
+
+    let all = SearchOptions::default();
+    let none = SearchOptions {
+        null_move: false,
+        late_move_reductions: false,
+        pvs: false,
+        check_extensions: false,
+    };
+    for &options in &[
+        all,
+        none,
+        SearchOptions {
+            null_move: false,
+            ..all
+        },
+        SearchOptions {
+            late_move_reductions: false,
+            ..all
+        },
+        SearchOptions { pvs: false, ..all },
+        SearchOptions {
+            check_extensions: false,
+            ..all
+        },
+    ] {
+        let mut searcher = Searcher::new(1);
+        searcher.options = options;
+        let result = searcher.search(&chess, &limits);
+
+        assert_eq!(result.best_move.map(String::from).as_deref(), Some("d5f6"));
+        assert_eq!(result.mate_in(), Some(2), "{:?}", options);
+    }
+}
+
+#[test]
+fn null_move() {
+    let mut chess = Chess::from_fen("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").unwrap();
+    let before = chess.clone();
+
+    let undo = chess.make_null_move();
+    assert_eq!(chess.turn, White);
+    assert_eq!(chess.en_passant, None);
+    assert_eq!(chess.hash, chess.compute_hash());
+    assert!(!chess.is_repetition());
+
+    chess.unmake_null_move(undo);
+    assert_eq!(chess.to_fen(), before.to_fen());
+    assert_eq!(chess.hash, before.hash);
+}
+
+fn millis(soft: u64, hard: u64) -> (Option<Duration>, Option<Duration>) {
+    (
+        Some(Duration::from_millis(soft)),
+        Some(Duration::from_millis(hard)),
+    )
+}
+
+#[test]
+fn time_budget() {
+    // 30ms of the clock are always kept for the move overhead
+    let clock = SearchLimits {
+        wtime: Some(60_030),
+        btime: Some(30_030),
+        ..SearchLimits::default()
+    };
+    assert_eq!(clock.time_budget(White), millis(2000, 8000));
+    assert_eq!(clock.time_budget(Black), millis(1000, 4000));
+
+    let increment = SearchLimits {
+        winc: Some(1000),
+        ..clock
+    };
+    assert_eq!(increment.time_budget(White), millis(2750, 11_000));
+    assert_eq!(increment.time_budget(Black), millis(1000, 4000));
+
+    // never more than what is left on the clock
+    let last_move = SearchLimits {
+        movestogo: Some(1),
+        ..increment
+    };
+    assert_eq!(last_move.time_budget(White), millis(60_000, 60_000));
+    let moves_to_go = SearchLimits {
+        movestogo: Some(10),
+        ..clock
+    };
+    assert_eq!(moves_to_go.time_budget(White), millis(6000, 24_000));
+    let no_moves_to_go = SearchLimits {
+        movestogo: Some(0),
+        ..clock
+    };
+    assert_eq!(no_moves_to_go.time_budget(Black), millis(30_000, 30_000));
+
+    let flagging = SearchLimits {
+        wtime: Some(10),
+        ..SearchLimits::default()
+    };
+    assert_eq!(flagging.time_budget(White), millis(0, 0));
+
+    let movetime = SearchLimits {
+        movetime: Some(1000),
+        ..clock
+    };
+    assert_eq!(movetime.time_budget(White), millis(970, 970));
+    assert_eq!(movetime.time_budget(Black), millis(970, 970));
+
+    let infinite = SearchLimits {
+        infinite: true,
+        ..movetime
+    };
+    assert_eq!(infinite.time_budget(White), (None, None));
+
+    let depth = SearchLimits {
+        depth: Some(5),
+        btime: Some(1000),
+        ..SearchLimits::default()
+    };
+    assert_eq!(depth.time_budget(White), (None, None));
+}
+
+#[test]
+fn node_limit() {
+    let limits = SearchLimits {
+        nodes: Some(5000),
+        ..SearchLimits::default()
+    };
+    let result = Searcher::new(1).search(&Chess::new(), &limits);
+
+    // the limit is checked every 1024 nodes
+    assert!(result.nodes < 5000 + 1024, "{}", result.nodes);
+    assert!(result.best_move.is_some());
+    assert!(result.depth >= 1);
+}
+
+#[test]
+fn quiescence_sees_recaptures() {
+    // the rook on d5 is defended by the pawn, the pawn on a4 is free
+    let result = search("6k1/8/4p3/3r4/p7/8/8/3Q2K1 w - - 0 1", 1);
+
+    assert_eq!(result.best_move.map(String::from).as_deref(), Some("d1a4"));
+    // a queen against a rook, not a queen against a pawn
+    assert!((200..600).contains(&result.score), "{}", result.score);
+}
+
+fn result(score: i32, nodes: u64, elapsed: Duration) -> SearchResult {
+    SearchResult {
+        best_move: None,
+        score,
+        pv: Vec::new(),
+        depth: 1,
+        seldepth: 1,
+        nodes,
+        elapsed,
+    }
+}
+
+#[test]
+fn mate_in() {
+    let mate_in = |score| result(score, 0, Duration::ZERO).mate_in();
+
+    assert_eq!(mate_in(MATE - 1), Some(1));
+    assert_eq!(mate_in(MATE - 2), Some(1));
+    assert_eq!(mate_in(MATE - 3), Some(2));
+    assert_eq!(mate_in(-MATE), Some(0));
+    assert_eq!(mate_in(-MATE + 2), Some(-1));
+    assert_eq!(mate_in(-MATE + 4), Some(-2));
+
+    assert_eq!(mate_in(0), None);
+    assert_eq!(mate_in(-350), None);
+    assert_eq!(mate_in(MATE - MAX_PLY), None);
+    assert_eq!(mate_in(-MATE + MAX_PLY), None);
+}
+
+#[test]
+fn nps() {
+    assert_eq!(result(0, 5000, Duration::from_millis(250)).nps(), 20_000);
+    assert_eq!(result(0, 1500, Duration::from_secs(3)).nps(), 500);
+    // less than a millisecond is not enough to tell
+    assert_eq!(result(0, 5000, Duration::from_micros(500)).nps(), 0);
+    assert_eq!(result(0, 5000, Duration::ZERO).nps(), 0);
+}
+
+#[test]
+fn full_pv_from_a_warm_table() {
+    let chess = Chess::new();
+    let limits = SearchLimits {
+        depth: Some(5),
+        ..SearchLimits::default()
+    };
+    let mut searcher = Searcher::new(1);
+
+    let first = searcher.search(&chess, &limits);
+    let second = searcher.search(&chess, &limits);
+    assert_eq!(first.pv.len(), 5);
+    assert_eq!(second.pv.len(), 5, "{:?}", second.pv);
+}
+
+#[test]
+fn time_runs_from_ponderhit() {
+    let limits = SearchLimits {
+        movetime: Some(330),
+        ..SearchLimits::default()
+    };
+    let mut searcher = Searcher::new(1);
+    let pondering = searcher.ponder_flag();
+    pondering.store(true, Ordering::SeqCst);
+
+    let start = Instant::now();
+    let search = thread::spawn(move || searcher.search(&Chess::new(), &limits));
+    thread::sleep(Duration::from_millis(500));
+    pondering.store(false, Ordering::SeqCst);
+    search.join().unwrap();
+    let elapsed = start.elapsed();
+
+    // the 300ms left after the move overhead only start to count at the ponderhit
+    assert!(elapsed >= Duration::from_millis(750), "{:?}", elapsed);
+}
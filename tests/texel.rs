@@ -0,0 +1,92 @@
+use chess_engine2::{
+    find_k, tunable_values, Color::White, EvalParams, TuningEntry, TuningSet, DEFAULT_PARAMS,
+};
+
+const AFTER_E4: &str = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3";
+
+fn parse(line: &str) -> (String, f64) {
+    let entry = TuningEntry::parse(line).unwrap();
+    (entry.chess.to_fen(), entry.result)
+}
+
+#[test]
+fn parses_entries() {
+    // missing move counters default to 0 1
+    let fen = format!("{} 0 1", AFTER_E4);
+
+    assert_eq!(parse(&format!("{} 0 1 1-0", AFTER_E4)), (fen.clone(), 1.0));
+    assert_eq!(parse(&format!("{} 0 1 0-1", AFTER_E4)), (fen.clone(), 0.0));
+    assert_eq!(
+        parse(&format!("{} 0 1 1/2-1/2", AFTER_E4)),
+        (fen.clone(), 0.5)
+    );
+    assert_eq!(
+        parse("8/8/4k3/8/8/4K3/4P3/8 w - - 12 40 [1.0]"),
+        ("8/8/4k3/8/8/4K3/4P3/8 w - - 12 40".to_string(), 1.0)
+    );
+
+    assert_eq!(parse(&format!("{} [0.5]", AFTER_E4)), (fen.clone(), 0.5));
+    assert_eq!(parse(&format!("{} [0.0]", AFTER_E4)), (fen.clone(), 0.0));
+    assert_eq!(parse(&format!("{} 1-0", AFTER_E4)), (fen, 1.0));
+
+    assert!(TuningEntry::parse(AFTER_E4).is_none());
+    assert!(TuningEntry::parse("8/8/8/8 w - - 1-0").is_none());
+}
+
+#[test]
+fn finds_k() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1",
+        "r1bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+        "4k3/4p3/8/8/8/8/8/R3K3 b - - 0 1",
+        "4k3/pppp4/8/8/8/8/8/4K3 w - - 0 1",
+    ];
+    // results that the evaluation predicts exactly with a K of 1.5
+    let entries: Vec<TuningEntry> = fens
+        .iter()
+        .map(|fen| {
+            let entry = TuningEntry::parse(&format!("{} 1/2-1/2", fen)).unwrap();
+            let score = entry.chess.evaluate_with(White, &DEFAULT_PARAMS) as f64;
+            let result = 1.0 / (1.0 + 10f64.powf(-1.5 * score / 400.0));
+            TuningEntry { result, ..entry }
+        })
+        .collect();
+
+    let set = TuningSet::new(entries);
+
+    let k = find_k(&set, &DEFAULT_PARAMS);
+    assert!((k - 1.5).abs() < 1e-3, "{}", k);
+    assert!(set.error(&DEFAULT_PARAMS, k) < 1e-9);
+    assert!(set.error(&DEFAULT_PARAMS, 1.0) > 1e-4);
+    assert_eq!(
+        set.error(&DEFAULT_PARAMS, 1.0),
+        set.error(&DEFAULT_PARAMS, 1.0)
+    );
+}
+
+#[test]
+fn skips_unused_values() {
+    let params = EvalParams::default();
+    let fields = params.fields();
+    let values = tunable_values(&params);
+    let total: usize = fields.iter().map(|(_, scores)| scores.len() * 2).sum();
+
+    let tuned = |name: &str, index: usize| {
+        values
+            .iter()
+            .any(|&(field, value, _)| fields[field].0 == name && value == index)
+    };
+    assert!(!tuned("material", 0));
+    assert!(tuned("material", 1));
+    assert!(!tuned("psqt_pawn", 3));
+    assert!(!tuned("psqt_pawn", 60));
+    assert!(tuned("psqt_pawn", 12));
+    assert!(tuned("psqt_king", 60));
+    assert!(!tuned("passed", 7));
+    assert!(tuned("passed", 6));
+    assert!(!tuned("mobility", 5));
+    assert!(tuned("mobility", 1));
+    assert_eq!(values.len(), total - 2 * (1 + 16 + 4 * 2 + 2));
+}
@@ -0,0 +1,62 @@
+use chess_engine2::{score_from_tt, Bound, TranspositionTable, MATE};
+
+// a table with a single slot, so that every key collides
+fn tiny_table() -> TranspositionTable {
+    TranspositionTable::new(0)
+}
+
+#[test]
+fn mate_scores_are_shifted_by_ply() {
+    let mut tt = TranspositionTable::new(1);
+
+    // mate found 3 plies below a node at ply 5, probed from the same position at ply 2
+    tt.store(42, 4, Bound::Exact, MATE - 8, None, 5);
+    let entry = tt.probe(42).unwrap();
+    assert_eq!(score_from_tt(entry.score, 2), MATE - 8 + 5 - 2);
+    assert_eq!(score_from_tt(entry.score, 5), MATE - 8);
+
+    tt.store(43, 4, Bound::Exact, -MATE + 8, None, 5);
+    let entry = tt.probe(43).unwrap();
+    assert_eq!(score_from_tt(entry.score, 2), -MATE + 8 - 5 + 2);
+
+    // ordinary scores are stored as they are
+    tt.store(44, 4, Bound::Exact, 150, None, 5);
+    assert_eq!(score_from_tt(tt.probe(44).unwrap().score, 2), 150);
+}
+
+#[test]
+fn deeper_entries_are_kept_on_collisions() {
+    let mut tt = tiny_table();
+
+    tt.store(1, 6, Bound::Exact, 10, None, 0);
+    tt.store(2, 3, Bound::Exact, 20, None, 0);
+    assert_eq!(tt.probe(1).unwrap().depth, 6);
+    assert!(tt.probe(2).is_none());
+
+    tt.store(2, 6, Bound::Lower, 20, None, 0);
+    assert!(tt.probe(1).is_none());
+    assert_eq!(tt.probe(2).unwrap().score, 20);
+}
+
+#[test]
+fn old_entries_are_replaced() {
+    let mut tt = tiny_table();
+
+    tt.store(1, 6, Bound::Exact, 10, None, 0);
+    tt.new_search();
+    tt.store(2, 3, Bound::Exact, 20, None, 0);
+    assert!(tt.probe(1).is_none());
+    assert_eq!(tt.probe(2).unwrap().depth, 3);
+}
+
+#[test]
+fn same_position_keeps_deeper_bound() {
+    let mut tt = tiny_table();
+
+    tt.store(1, 6, Bound::Lower, 10, None, 0);
+    tt.store(1, 3, Bound::Upper, 20, None, 0);
+    assert_eq!(tt.probe(1).unwrap().depth, 6);
+
+    tt.store(1, 3, Bound::Exact, 30, None, 0);
+    assert_eq!(tt.probe(1).unwrap().score, 30);
+}
@@ -0,0 +1,53 @@
+use std::convert::TryInto;
+
+use chess_engine2::Chess;
+
+fn play(fen: &str, moves: &[&str]) -> Chess {
+    let mut chess = Chess::from_fen(fen).unwrap();
+    for &r#move in moves {
+        chess.set(r#move.try_into().unwrap());
+        assert_eq!(chess.hash, chess.compute_hash(), "after {}", r#move);
+    }
+    chess
+}
+
+#[test]
+fn transpositions_hash_equal() {
+    let startpos = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    let a = play(startpos, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+    let b = play(startpos, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+    assert_eq!(a.hash, b.hash);
+
+    let c = play(startpos, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+    assert_eq!(c.hash, Chess::new().hash);
+}
+
+#[test]
+fn state_changes_hash() {
+    let startpos = Chess::new();
+    assert_ne!(startpos.hash, startpos.clone().invert_turn().hash);
+
+    let castles = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let rook_moved = play(castles, &["h1h2", "a8a7", "h2h1", "a7a8"]);
+    assert_ne!(rook_moved.hash, Chess::from_fen(castles).unwrap().hash);
+    assert_eq!(
+        rook_moved.hash,
+        Chess::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Qk - 0 1").unwrap().hash
+    );
+}
+
+#[test]
+fn en_passant_hashed_only_when_capturable() {
+    let with_capture = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+    let without_capture = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1";
+
+    assert_ne!(
+        Chess::from_fen(with_capture).unwrap().hash,
+        Chess::from_fen(&with_capture.replace("e3", "-")).unwrap().hash
+    );
+    assert_eq!(
+        Chess::from_fen(without_capture).unwrap().hash,
+        Chess::from_fen(&without_capture.replace("e3", "-")).unwrap().hash
+    );
+}